@@ -35,12 +35,17 @@ pub struct Config {
     pub bochs_args: Vec<String>,
     pub mutator_seed: Option<usize>,
     pub seeds_dir: Option<String>,
+    pub seeds_pack: Option<String>,
+    pub pack_seeds: Option<String>,
     pub output_dir: String,
     pub findings_limit: usize,
     pub stat_interval: u128,
     pub sync_interval: usize,
     pub icount_timeout: usize,
     pub num_fuzzers: usize,
+    pub corpus_mem_limit: Option<usize>,
+    pub evict_lru: bool,
+    pub compress_findings: bool,
 }
 
 /// Parses the command line arguments and creates a Config which is used to
@@ -64,6 +69,14 @@ pub fn parse_args() -> Result<Config, LucidErr> {
         .long("seeds-dir")
         .value_name("SEEDS_DIR")
         .help("Directory containing seed inputs (optional)"))
+    .arg(Arg::new("seeds-pack")
+        .long("seeds-pack")
+        .value_name("PACK")
+        .help("Packed corpus file containing seed inputs (optional)"))
+    .arg(Arg::new("pack-seeds")
+        .long("pack-seeds")
+        .value_name("PACK")
+        .help("Writes the seed inputs to a packed corpus file for --seeds-pack"))
     .arg(Arg::new("output-dir")
         .long("output-dir")
         .value_name("OUTPUT_DIR")
@@ -101,6 +114,18 @@ pub fn parse_args() -> Result<Config, LucidErr> {
         .long("icount-timeout")
         .value_name("INSTRUCTION_COUNT")
         .help("Number of instructions we can execute before a timeout (in millions)"))
+    .arg(Arg::new("corpus-mem-limit")
+        .long("corpus-mem-limit")
+        .value_name("LIMIT")
+        .help("Number of megabytes the in-memory corpus can hold (unlimited default)"))
+    .arg(Arg::new("evict-lru")
+        .long("evict-lru")
+        .help("Evict least recently used inputs at the corpus memory limit")
+        .action(ArgAction::SetTrue))
+    .arg(Arg::new("compress-findings")
+        .long("compress-findings")
+        .help("Gzip crashes and timeouts saved to disk")
+        .action(ArgAction::SetTrue))
     .arg(Arg::new("bochs-image")
         .long("bochs-image")
         .value_name("IMAGE")
@@ -163,6 +188,10 @@ pub fn parse_args() -> Result<Config, LucidErr> {
     let seeds_str = matches.get_one::<String>("seeds-dir");
     let seeds_dir = seeds_str.map(|str_repr| str_repr.to_string());
 
+    // See if a packed seeds file was provided, or one should be written
+    let seeds_pack = matches.get_one::<String>("seeds-pack").cloned();
+    let pack_seeds = matches.get_one::<String>("pack-seeds").cloned();
+
     // See if a findings limit was provided
     let limit_str = matches.get_one::<String>("findings-limit");
     let findings_limit = match limit_str {
@@ -272,6 +301,25 @@ pub fn parse_args() -> Result<Config, LucidErr> {
         }
     };
 
+    // See if an in-memory corpus limit was provided
+    let limit_str = matches.get_one::<String>("corpus-mem-limit");
+    let corpus_mem_limit = match limit_str {
+        None => None,
+        Some(str_repr) => {
+            let Ok(limit) = str_repr.parse::<usize>() else {
+                return Err(LucidErr::from("Invalid --corpus-mem-limit"));
+            };
+
+            // Multiply the passed in limit by a megabyte
+            let limit = limit.wrapping_mul(MEG);
+            prompt!("Corpus memory limit set to {}MB", limit / MEG);
+
+            Some(limit)
+        }
+    };
+    let evict_lru = matches.get_flag("evict-lru");
+    let compress_findings = matches.get_flag("compress-findings");
+
     // Create and return Config
     Ok(Config {
         input_max_size,
@@ -282,11 +330,16 @@ pub fn parse_args() -> Result<Config, LucidErr> {
         bochs_args,
         mutator_seed,
         seeds_dir,
+        seeds_pack,
+        pack_seeds,
         output_dir,
         findings_limit,
         stat_interval,
         sync_interval,
         icount_timeout,
         num_fuzzers,
+        corpus_mem_limit,
+        evict_lru,
+        compress_findings,
     })
 }
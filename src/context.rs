@@ -5,6 +5,9 @@
 //! r15 -- Pointer to a LucidContext
 
 use std::arch::{asm, global_asm};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
 
 use crate::config::Config;
 use crate::corpus::Corpus;
@@ -15,12 +18,12 @@ use crate::loader::Bochs;
 use crate::misc::PAGE_SIZE;
 use crate::misc::{fxrstor64, fxsave64, get_xcr0, xrstor64, xsave64};
 use crate::mmu::Mmu;
-use crate::mutator::Mutator;
+use crate::mutator::{save_record, MutationTypes, Mutator};
 use crate::redqueen::{lucid_report_cmps, redqueen_pass, Redqueen};
 use crate::snapshot::{restore_snapshot, take_snapshot, Snapshot};
 use crate::stats::{CorpusStats, SnapshotStats, Stats};
 use crate::syscall::lucid_syscall;
-use crate::{fault, finding, mega_panic, prompt_warn};
+use crate::{fault, finding, finding_warn, mega_panic, prompt_warn};
 
 /// Magic number member of the LucidContext, chosen by ChatGPT, that we use to
 /// ensure that the context pointer we receive during context switches is
@@ -213,6 +216,33 @@ pub enum FuzzingResult {
     NewCoverage,
 }
 
+/// What a fuzzcase that didn't crash or time out did, this is what an Oracle
+/// judges an input by
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct ExecResult<'a> {
+    pub new_coverage: bool, // Whether the default coverage rule would admit it
+    pub coverage: &'a [u8], // Raw edge pair hit counts for the fuzzcase
+}
+
+/// A pluggable "interestingness" judge for fuzzing results. By default, inputs
+/// are admitted to the corpus when they reach new coverage, if an Oracle is
+/// installed in the LucidContext it decides corpus admission instead. Crashes
+/// and timeouts are handled the same way regardless of the Oracle
+pub trait Oracle {
+    fn judge(&mut self, input: &[u8], result: &ExecResult) -> bool;
+}
+
+/// Builds the Mutator that the configuration asks for
+pub fn create_mutator(config: &Config) -> Result<Mutator, LucidErr> {
+    let mut builder = Mutator::builder().max_size(config.input_max_size);
+    if let Some(seed) = config.mutator_seed {
+        builder = builder.seed(seed);
+    }
+
+    builder.build()
+}
+
 /// Represents the type of set-up we have, are we single or multi-process, this
 /// informs quite a bit of logic elsewhere in the codebase, specifically stat
 /// reporting
@@ -289,6 +319,7 @@ pub struct LucidContext {
     pub fuzzing_stage: FuzzingStage, // Dictates logic for running inputs
     pub fuzzer_id: usize,       // The id for the fuzzer process
     pub exec_arch: ExecArch,    // The type of architecture we're using
+    pub oracle: Option<Rc<RefCell<Box<dyn Oracle>>>>, // Overrides corpus admission
 }
 
 impl LucidContext {
@@ -440,6 +471,13 @@ impl LucidContext {
         }
     }
 
+    /// Installs an Oracle that decides corpus admission in place of the default
+    /// new coverage rule
+    #[allow(dead_code)]
+    pub fn set_oracle(&mut self, oracle: Box<dyn Oracle>) {
+        self.oracle = Some(Rc::new(RefCell::new(oracle)));
+    }
+
     /// Checks the fuzzing flag which means we've begun fuzzing
     #[inline]
     pub fn is_fuzzing(&self) -> bool {
@@ -500,7 +538,7 @@ impl LucidContext {
         let coverage_map_size = coverage.curr_map.len();
 
         // Create mutator
-        let mutator = Mutator::new(config.mutator_seed, config.input_max_size);
+        let mutator = create_mutator(config)?;
 
         // Determine execution architecture
         let exec_arch = if config.num_fuzzers == 1 {
@@ -557,6 +595,7 @@ impl LucidContext {
             dirty_block_length: snapshot.dirty_block_length,
             new_dirty_page: 0,
             snapshot,
+            oracle: None,
         })
     }
 }
@@ -1039,6 +1078,11 @@ fn generate_input(context: &mut LucidContext) {
     else {
         context.mutator.mutate_input(&context.corpus);
 
+        // Keep the parent from being evicted under a corpus memory limit
+        if let Some(idx) = context.mutator.last_parent() {
+            context.corpus.touch(idx);
+        }
+
         // Update the fuzzing stage
         context.fuzzing_stage = FuzzingStage::Fuzzing;
     }
@@ -1084,7 +1128,8 @@ pub fn run_fuzzcase(context: &mut LucidContext) -> Result<(), LucidErr> {
 /// new code. As of now, crashes are not saved into the corpus for re-running
 pub fn handle_crash(context: &mut LucidContext) {
     // Save crash
-    context.corpus.save_crash(&context.mutator.input, "crash");
+    let hash = context.corpus.save_crash(&context.mutator.input, "crash");
+    report_provenance(context, hash, "crash");
 
     // Update coverage
     context.coverage.update_coverage();
@@ -1095,12 +1140,90 @@ pub fn handle_crash(context: &mut LucidContext) {
     context.stats.new_coverage(edges);
 }
 
+/// Saves the corpus id and content hash of the input a finding descended from
+/// as a line of text, so it can be read without any tooling
+fn save_parent(path: &std::path::Path, parent: usize, parent_hash: u64) -> std::io::Result<()> {
+    std::fs::write(path, format!("{} {:016X}\n", parent, parent_hash))
+}
+
+/// Saves the mutator's session history as text, one line per mutated input
+/// with the oldest input first
+fn save_history(
+    path: &std::path::Path,
+    history: &VecDeque<Vec<MutationTypes>>,
+) -> std::io::Result<()> {
+    let lines: String = history
+        .iter()
+        .map(|sequence| format!("{:?}\n", sequence))
+        .collect();
+    std::fs::write(path, lines)
+}
+
+/// Reports which corpus input a saved finding was mutated from, if any, so a
+/// crash can be attributed to the seed it descended from. The parent and the
+/// mutation rounds are saved next to the finding so it can be rebuilt with
+/// `reproduce`, along with the session history if the mutator keeps one
+fn report_provenance(context: &LucidContext, hash: u64, filetype: &str) {
+    let crash_dir = std::path::Path::new(&context.corpus.crash_dir);
+
+    // Save what the mutator has been up to recently
+    let history = context.mutator.history();
+    if !history.is_empty() {
+        let history_path = crash_dir.join(format!("{:016X}.{}.history", hash, filetype));
+        if save_history(&history_path, history).is_err() {
+            finding_warn!(
+                context.fuzzer_id,
+                "Unable to save mutation history for {} input {:016X}",
+                filetype,
+                hash
+            );
+        }
+    }
+
+    let Some(parent) = context.mutator.last_parent() else {
+        return;
+    };
+
+    let parent_hash = context.corpus.content_hash(parent).unwrap_or(0);
+    finding!(
+        context.fuzzer_id,
+        "{} input {:016X} descended from corpus input {} ({:016X})",
+        filetype,
+        hash,
+        parent,
+        parent_hash
+    );
+
+    // Save which input the finding descended from right next to it
+    let parent_path = crash_dir.join(format!("{:016X}.{}.parent", hash, filetype));
+    if save_parent(&parent_path, parent, parent_hash).is_err() {
+        finding_warn!(
+            context.fuzzer_id,
+            "Unable to save parent for {} input {:016X}",
+            filetype,
+            hash
+        );
+    }
+
+    // Save how to rebuild the finding from its parent right next to it
+    let record_path = crash_dir.join(format!("{:016X}.{}.record", hash, filetype));
+    if save_record(&record_path, parent_hash, context.mutator.last_records()).is_err() {
+        finding_warn!(
+            context.fuzzer_id,
+            "Unable to save mutation record for {} input {:016X}",
+            filetype,
+            hash
+        );
+    }
+}
+
 /// If the fuzzing iteration detects a timeout, save the timeout to disk in the
 /// corpus timeout directory, update the coverage metrics if the timeout reached
 /// any new code. As of now, timeouts are not saved into the corpus for re-running
 pub fn handle_timeout(context: &mut LucidContext) {
     // Save timeout
-    context.corpus.save_crash(&context.mutator.input, "timeout");
+    let hash = context.corpus.save_crash(&context.mutator.input, "timeout");
+    report_provenance(context, hash, "timeout");
 
     // Update coverage
     context.coverage.update_coverage();
@@ -1115,7 +1238,20 @@ pub fn handle_timeout(context: &mut LucidContext) {
 /// input to the corpus, get a new edge-count, update the coverage statistics,
 /// place the current input into Redqueen's queue to process
 pub fn handle_new_coverage(context: &mut LucidContext, old_edge_count: usize) -> usize {
+    // Remember which input this one was mutated from if it made it in, making
+    // room for it can evict inputs and shift the parent's index so resolve
+    // the parent's id first. Ids only grow, so a newer last id means it's ours
+    let parent = context
+        .mutator
+        .last_parent()
+        .and_then(|idx| context.corpus.id_of(idx));
+    let newest = context.corpus.metas.last().map(|meta| meta.id);
     context.corpus.save_input(&context.mutator.input);
+    if let Some(meta) = context.corpus.metas.last_mut() {
+        if newest.is_none_or(|id| meta.id > id) {
+            meta.parent = parent;
+        }
+    }
     let new_edge_count = context.coverage.get_edge_count();
     finding!(
         context.fuzzer_id,
@@ -1185,8 +1321,15 @@ pub fn fuzz_one(context: &mut LucidContext) -> Result<FuzzingResult, LucidErr> {
         fuzzing_result = FuzzingResult::Timeout;
         context.timeout = 0;
     }
-    // Check for coverage increase
-    else if time_func!(context, batch_coverage, context.coverage.update_coverage()) {
+    // Check for coverage increase, or let the Oracle decide if we have one
+    else if time_func!(context, batch_coverage, {
+        let mut oracle = context.oracle.as_ref().map(|oracle| oracle.borrow_mut());
+        admit_input(
+            &mut context.coverage,
+            oracle.as_deref_mut().map(|oracle| &mut **oracle),
+            &context.mutator.input,
+        )
+    }) {
         fuzzing_result = FuzzingResult::NewCoverage;
     }
 
@@ -1194,20 +1337,47 @@ pub fn fuzz_one(context: &mut LucidContext) -> Result<FuzzingResult, LucidErr> {
     Ok(fuzzing_result)
 }
 
+/// Decides whether an input that didn't crash or time out is admitted to the
+/// corpus. Without an Oracle that's whenever it reached new coverage, with one
+/// the Oracle decides, overriding the new coverage rule. Coverage is only
+/// recorded for admitted inputs so that the edges of a rejected input are
+/// still new to the next input that reaches them
+fn admit_input(
+    coverage: &mut CoverageMap,
+    oracle: Option<&mut (dyn Oracle + 'static)>,
+    input: &[u8],
+) -> bool {
+    // No Oracle, keep the default coverage rule
+    let Some(oracle) = oracle else {
+        return coverage.update_coverage();
+    };
+
+    // Ask the Oracle before anything is recorded
+    let result = ExecResult {
+        new_coverage: coverage.has_new_coverage(),
+        coverage: &coverage.curr_map,
+    };
+
+    if oracle.judge(input, &result) {
+        coverage.update_coverage();
+        true
+    } else {
+        coverage.discard_coverage();
+        false
+    }
+}
+
 /// Execute all of the inputs found in the seeds directory so that we get a
 /// coverage baseline. This function will handle crashes and timeouts in the
-/// same way that it would if they were discovered via fuzzing
+/// same way that it would if they were discovered via fuzzing, and an Oracle
+/// decides which seeds make it into the baseline just like it would for
+/// fuzzed inputs
 pub fn dry_run(context: &mut LucidContext) -> Result<(), LucidErr> {
     // Set the context fuzzing stage to dry run
     context.fuzzing_stage = FuzzingStage::DryRun;
 
-    // For the number of inputs in the corpus, fuzz one
-    for i in 0..context.corpus.num_inputs() {
-        // Place the seed input in the mutator buf
-        context
-            .mutator
-            .memcpy_input(context.corpus.get_input(i).unwrap());
-
+    // Walk the corpus once, each seed is placed in the mutator buf in turn
+    while context.mutator.corpus_walk(&context.corpus).is_some() {
         // Run the input through
         let result = fuzz_one(context);
 
@@ -1306,12 +1476,12 @@ pub fn fuzz_loop(context: &mut LucidContext, id: Option<usize>) -> Result<(), Lu
     // Keep track of old edge count
     let mut old_edge_count = context.coverage.get_edge_count();
 
-    // If we're fuzzing multi-process style re-seed the prng
-    if context.fuzzer_id != 0 {
+    // If we're fuzzing multi-process style we were given our own pRNG stream
+    if id.is_some() {
         finding!(
             context.fuzzer_id,
             "Re-seeded pRNG to: 0x{:X}",
-            context.mutator.reseed()
+            context.mutator.rng_state()
         );
     }
 
@@ -1359,3 +1529,87 @@ pub fn fuzz_loop(context: &mut LucidContext, id: Option<usize>) -> Result<(), Lu
         context.corpus.sync();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Admits only inputs longer than 10 bytes, regardless of coverage
+    struct LongInputs;
+
+    impl Oracle for LongInputs {
+        fn judge(&mut self, input: &[u8], _result: &ExecResult) -> bool {
+            input.len() > 10
+        }
+    }
+
+    #[test]
+    fn oracle_decides_admission() {
+        let mut coverage = CoverageMap::new();
+        let mut corpus = Corpus::with_capacity(0);
+        let mut oracle = LongInputs;
+
+        for len in 1..=20 {
+            // Every input reaches an edge pair nothing else has
+            coverage.curr_map[len] = 1;
+
+            let input = vec![b'A'; len];
+            if admit_input(&mut coverage, Some(&mut oracle), &input) {
+                corpus.add_input(input);
+            }
+        }
+
+        assert_eq!(corpus.num_inputs(), 10);
+        assert!(corpus.inputs.iter().all(|input| input.len() > 10));
+    }
+
+    #[test]
+    fn rejected_input_doesnt_record_coverage() {
+        let mut coverage = CoverageMap::new();
+        let mut oracle = LongInputs;
+
+        // A short input reaching the edge pair is rejected
+        coverage.curr_map[7] = 1;
+        assert!(!admit_input(&mut coverage, Some(&mut oracle), b"short"));
+        assert_eq!(coverage.get_edge_count(), 0);
+
+        // So the edge pair is still new coverage for a later input
+        coverage.curr_map[7] = 1;
+        assert!(admit_input(&mut coverage, None, b"short"));
+        assert_eq!(coverage.get_edge_count(), 1);
+    }
+
+    #[test]
+    fn parent_is_saved_as_text() {
+        let dir = std::env::temp_dir().join(format!("lucid_parent_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("00000000DEADBEEF.crash.parent");
+        save_parent(&path, 3, 0x1234).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "3 0000000000001234\n"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn history_is_saved_as_text() {
+        let dir = std::env::temp_dir().join(format!("lucid_history_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let history = VecDeque::from([
+            vec![MutationTypes::BitFlip],
+            vec![MutationTypes::Splice, MutationTypes::Grow],
+        ]);
+        let path = dir.join("00000000DEADBEEF.crash.history");
+        save_history(&path, &history).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "[BitFlip]\n[Splice, Grow]\n"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,16 +1,143 @@
 //! This file contains all of the datastructures and logic necessary to create
 //! and manage a corpus of inputs for fuzzing
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::hash::{DefaultHasher, Hash, Hasher};
-use std::io::Read;
+use std::io::{Read, Write};
+use std::sync::Arc;
 use std::time::Instant;
 
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
 use crate::config::Config;
 use crate::err::LucidErr;
 use crate::misc::MEG;
-use crate::{finding, finding_warn, prompt_warn};
+use crate::packed::{pack_corpus, PackedCorpus};
+use crate::{finding, finding_warn, prompt, prompt_warn};
+
+/// Every gzip stream starts with these bytes
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+
+/// The most n-grams of a recipient that are looked up when searching for a
+/// compatible splice donor, longer recipients are sampled at an even stride
+const MAX_RECIPIENT_NGRAMS: usize = 256;
+
+/// Per-input bookkeeping that is kept in lock-step with the in-memory inputs,
+/// index `i` in `Corpus::metas` always describes index `i` in `Corpus::inputs`
+#[derive(Clone, Default)]
+pub struct InputMeta {
+    pub favored: bool,              // Input is part of the favored covering set
+    coverage: Option<HashSet<u64>>, // Cached coverage, None when not computed
+    pub id: u64,                    // Stable id, survives removals and shuffles
+    pub parent: Option<u64>,        // Id of the input this was mutated from
+    pub tag: Option<String>,        // Format of the input (ie "png"), if known
+    priority: Option<f64>,          // User score for priority selection
+    last_used: u64,                 // Corpus clock when last added or touched
+}
+
+/// A point-in-time summary of the in-memory corpus for status lines and
+/// dashboards, not to be confused with `stats::CorpusStats` which only carries
+/// what the stat reporter needs every iteration
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CorpusStats {
+    pub count: usize,       // Number of inputs
+    pub total_bytes: usize, // Sum of all input lengths
+    pub min_len: usize,     // Length of the smallest input
+    pub max_len: usize,     // Length of the largest input
+    pub avg_len: f64,       // Average input length
+    pub favored: usize,     // Number of favored inputs
+    pub covered: usize,     // Number of inputs with cached coverage
+}
+
+/// A read-only view of a set of inputs, this is everything the Mutator needs
+/// from a corpus so it can mutate out of anything that hands out input slices
+pub trait CorpusView {
+    /// Return the number of inputs in the view
+    fn num_inputs(&self) -> usize;
+
+    /// Retrieves a reference to an input or None if `idx` is out of range
+    fn get_input(&self, idx: usize) -> Option<&[u8]>;
+
+    /// Computes the content hash of an input, this is the same hash used to
+    /// name inputs on disk
+    fn content_hash(&self, idx: usize) -> Option<u64> {
+        let input = self.get_input(idx)?;
+
+        let mut hasher = DefaultHasher::new();
+        input.hash(&mut hasher);
+        Some(hasher.finish())
+    }
+
+    /// Finds a splice donor that shares byte patterns with `recipient`, `rng`
+    /// is drawn from to sample the donor. Views without an n-gram index don't
+    /// have an opinion
+    fn splice_compatible(
+        &self,
+        _recipient: &[u8],
+        _rng: &mut dyn FnMut() -> usize,
+    ) -> Option<usize> {
+        None
+    }
+
+    /// Retrieves how many edges an input is known to cover, views that don't
+    /// track coverage return None
+    fn coverage_size(&self, _idx: usize) -> Option<usize> {
+        None
+    }
+
+    /// Retrieves an input's format tag, views that don't tag inputs return
+    /// None
+    fn tag(&self, _idx: usize) -> Option<&str> {
+        None
+    }
+
+    /// Retrieves an input's user-assigned selection priority, views that don't
+    /// track priorities return None
+    fn priority(&self, _idx: usize) -> Option<f64> {
+        None
+    }
+
+    /// Retrieves a version that changes whenever the inputs or their
+    /// per-input data change, so the Mutator knows when its cached selection
+    /// weights are stale. Views that don't track changes return None and are
+    /// never cached
+    fn version(&self) -> Option<u64> {
+        None
+    }
+
+    /// Selects one of the inputs tagged with `tag`, `draw` is a random value
+    /// that picks which one. Returns None if no input has the tag
+    fn select_tagged(&self, tag: &str, draw: usize) -> Option<usize> {
+        let tagged = (0..self.num_inputs())
+            .filter(|&idx| self.tag(idx) == Some(tag))
+            .count();
+        if tagged == 0 {
+            return None;
+        }
+
+        (0..self.num_inputs())
+            .filter(|&idx| self.tag(idx) == Some(tag))
+            .nth(draw % tagged)
+    }
+}
+
+/// Maps hashed byte n-grams to the inputs that contain them so that splice
+/// can find donors that share byte patterns with the recipient
+#[derive(Clone, Default)]
+struct NgramIndex {
+    n: usize,                         // Length of the indexed n-grams
+    donors: HashMap<u64, Vec<usize>>, // N-gram hash -> ids of inputs with it
+}
+
+/// Maps an input to the canonical form that gets hashed for dedup, for
+/// instance by zeroing out timestamps or nonces
+pub type CanonFn = dyn Fn(&[u8]) -> Vec<u8> + Send + Sync;
+
+/// Shared so that the Corpus can still be cloned
+type CanonHook = Arc<CanonFn>;
 
 /// Holds all of the information and statistics we need in order to manage a
 /// database of inputs, timeouts, and crashes.
@@ -20,12 +147,22 @@ pub struct Corpus {
     pub crash_dir: String,      // Where crashes are written to on disk
     pub stats_dir: String,      // Where statistics are written to on disk
     pub inputs: Vec<Vec<u8>>,   // In memory input database
+    pub metas: Vec<InputMeta>,  // Bookkeeping for each in memory input
     input_hashes: HashSet<u64>, // Database of unique input hashes
     findings_limit: usize,      // The limit in megabytes of what we can save
+    compress_findings: bool,    // Gzip crashes and timeouts on disk
     pub id: usize,              // Inherited from the LucidContext
     last_sync: Instant,         // The last time we synced from disk to memory
     sync_interval: u64,         // How often we sync the in-memory corpus with the disk
     pub corpus_size: usize,     // The number of bytes in the corpus
+    ngrams: Option<NgramIndex>, // Splice donor lookup, None until built
+    next_input_id: u64,         // Stable id handed to the next new input
+    version: u64,               // Bumped whenever the inputs or their data change
+    canon: Option<CanonHook>,   // Optional dedup canonicalization hook
+    mem_limit: Option<usize>,   // Ceiling on corpus_size for add_input
+    evict_lru: bool,            // Evict least recently used inputs at the limit
+    clock: u64,                 // Ticks on every add and touch, for LRU
+    seen_files: HashSet<u64>,   // File name hashes already saved or synced
 }
 
 impl Corpus {
@@ -70,6 +207,16 @@ impl Corpus {
             }
         }
 
+        // Read in any seeds from a packed corpus file too
+        if let Some(seeds_pack) = &config.seeds_pack {
+            let packed = PackedCorpus::open(std::path::Path::new(seeds_pack))?;
+            for idx in 0..packed.num_inputs() {
+                let input = packed.get_input(idx).unwrap();
+                corpus_size += input.len();
+                inputs.push(input.to_vec());
+            }
+        }
+
         // Formulate dir names
         let inputs_dir = format!("{}/inputs", config.output_dir);
         let crash_dir = format!("{}/crashes", config.output_dir);
@@ -149,18 +296,229 @@ impl Corpus {
         // Count this now as our last sync
         let last_sync = Instant::now();
 
-        Ok(Corpus {
+        // Create bookkeeping for each of the seeds
+        let metas: Vec<InputMeta> = (0..inputs.len())
+            .map(|id| InputMeta {
+                id: id as u64,
+                ..Default::default()
+            })
+            .collect();
+        let next_input_id = metas.len() as u64;
+
+        let mut corpus = Corpus {
             inputs_dir,
             crash_dir,
             stats_dir,
             inputs,
+            metas,
             input_hashes: HashSet::new(),
             findings_limit: config.findings_limit,
+            compress_findings: config.compress_findings,
             id: 0,
             last_sync,
             sync_interval: config.sync_interval as u64,
             corpus_size,
-        })
+            ngrams: None,
+            next_input_id,
+            version: 0,
+            canon: None,
+            mem_limit: None,
+            evict_lru: false,
+            clock: 0,
+            seen_files: HashSet::new(),
+        };
+
+        // The seeds are all kept, the limit applies to what's added after
+        corpus.set_memory_limit(config.corpus_mem_limit, config.evict_lru);
+
+        // Write the seeds out as a packed corpus if asked to
+        if let Some(pack_seeds) = &config.pack_seeds {
+            pack_corpus(&corpus, std::path::Path::new(pack_seeds))?;
+            prompt!("Packed {} seeds into '{}'", corpus.num_inputs(), pack_seeds);
+        }
+
+        Ok(corpus)
+    }
+
+    /// Create an in-memory only Corpus from a list of inputs, duplicates are
+    /// dropped. There are no backing directories so nothing is ever synced
+    /// from disk and the findings limit is zero, meaning nothing is saved
+    pub fn from_inputs(inputs: Vec<Vec<u8>>) -> Self {
+        let mut corpus = Corpus::with_capacity(inputs.len());
+        corpus.add_inputs(inputs);
+        corpus
+    }
+
+    /// Create an empty in-memory only Corpus, like `from_inputs`, with room
+    /// for `n` inputs so that a large import doesn't keep reallocating
+    pub fn with_capacity(n: usize) -> Self {
+        Corpus {
+            inputs_dir: String::new(),
+            crash_dir: String::new(),
+            stats_dir: String::new(),
+            inputs: Vec::with_capacity(n),
+            metas: Vec::with_capacity(n),
+            input_hashes: HashSet::with_capacity(n),
+            findings_limit: 0,
+            compress_findings: false,
+            id: 0,
+            last_sync: Instant::now(),
+            sync_interval: u64::MAX,
+            corpus_size: 0,
+            ngrams: None,
+            next_input_id: 0,
+            version: 0,
+            canon: None,
+            mem_limit: None,
+            evict_lru: false,
+            clock: 0,
+            seen_files: HashSet::new(),
+        }
+    }
+
+    /// Reserves room for at least `additional` more inputs in the input
+    /// database, the bookkeeping, and the dedup hashes
+    pub fn reserve(&mut self, additional: usize) {
+        self.inputs.reserve(additional);
+        self.metas.reserve(additional);
+        self.input_hashes.reserve(additional);
+    }
+
+    /// Writes every in-memory input to `dir` gzipped, named by its content hash
+    /// with a `.input.gz` suffix, `dir` is created if it doesn't exist. Returns
+    /// the number of inputs written
+    #[allow(dead_code)]
+    pub fn save_to_dir_gz(&self, dir: &std::path::Path) -> Result<usize, LucidErr> {
+        std::fs::create_dir_all(dir).map_err(|e| {
+            LucidErr::Corpus(format!(
+                "Unable to create directory '{}', error: {}",
+                dir.display(),
+                e
+            ))
+        })?;
+
+        for (idx, input) in self.inputs.iter().enumerate() {
+            let hash = Corpus::content_hash(self, idx).unwrap_or(0);
+            let path = dir.join(format!("{:016X}.input.gz", hash));
+            std::fs::write(&path, gzip(input)?).map_err(|e| {
+                LucidErr::Corpus(format!(
+                    "Unable to write '{}', error: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+        }
+
+        Ok(self.inputs.len())
+    }
+
+    /// Create an in-memory only Corpus from every `.gz` file in `dir`, each
+    /// one is decompressed into an input. Other files are skipped, and a file
+    /// that isn't valid gzip is an error
+    #[allow(dead_code)]
+    pub fn load_from_dir_gz(dir: &std::path::Path) -> Result<Corpus, LucidErr> {
+        let entries = std::fs::read_dir(dir).map_err(|e| {
+            LucidErr::Corpus(format!(
+                "Unable to read directory '{}', error: {}",
+                dir.display(),
+                e
+            ))
+        })?;
+
+        // Sort the paths so the corpus order doesn't depend on the filesystem
+        let mut paths = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "gz") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let mut inputs = Vec::with_capacity(paths.len());
+        for path in paths {
+            let compressed = std::fs::read(&path)?;
+            inputs.push(gunzip(&compressed).map_err(|e| {
+                LucidErr::Corpus(format!("Unable to decompress '{}': {}", path.display(), e))
+            })?);
+        }
+
+        Ok(Corpus::from_inputs(inputs))
+    }
+
+    /// Create an in-memory only Corpus from the regular file entries of a tar
+    /// archive, directories and other entry types are skipped. Gzipped
+    /// tarballs are decompressed in memory first, zip files are rejected and
+    /// need to be unpacked first
+    #[allow(dead_code)]
+    pub fn load_from_archive(path: &std::path::Path) -> Result<Corpus, LucidErr> {
+        const BLOCK: usize = 512;
+
+        let archive = std::fs::read(path).map_err(|e| {
+            LucidErr::Corpus(format!(
+                "Unable to read archive '{}', error: {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        // Reject formats we can't unpack
+        if archive.starts_with(b"PK\x03\x04") {
+            return Err(LucidErr::Corpus(
+                "Zip archives aren't supported, provide a .tar or .tar.gz".to_string(),
+            ));
+        }
+
+        // Unpack a gzipped tarball
+        let archive = if archive.starts_with(&GZIP_MAGIC) {
+            gunzip(&archive).map_err(|e| {
+                LucidErr::Corpus(format!(
+                    "Unable to decompress archive '{}', error: {}",
+                    path.display(),
+                    e
+                ))
+            })?
+        } else {
+            archive
+        };
+
+        let mut inputs = Vec::new();
+        let mut offset = 0;
+        while offset + BLOCK <= archive.len() {
+            let header = &archive[offset..offset + BLOCK];
+
+            // An all-zero header marks the end of the archive
+            if header.iter().all(|&byte| byte == 0) {
+                break;
+            }
+
+            // Parse the size of the entry data
+            let Some(size) = parse_tar_size(&header[124..136]) else {
+                return Err(LucidErr::Corpus(
+                    "Malformed tar header size field".to_string(),
+                ));
+            };
+
+            // Make sure the data is actually there, a bogus size can't
+            // overflow past the end either
+            let data_start = offset + BLOCK;
+            let Some(data_end) = data_start
+                .checked_add(size)
+                .filter(|&end| end <= archive.len())
+            else {
+                return Err(LucidErr::Corpus("Truncated tar archive".to_string()));
+            };
+
+            // Only regular files become inputs
+            if matches!(header[156], b'0' | 0) {
+                inputs.push(archive[data_start..data_end].to_vec());
+            }
+
+            // Skip the data, which is padded out to a full block
+            offset = data_start + size.div_ceil(BLOCK) * BLOCK;
+        }
+
+        Ok(Corpus::from_inputs(inputs))
     }
 
     /// Return the number of inputs currently in the corpus in memory
@@ -178,6 +536,545 @@ impl Corpus {
         None
     }
 
+    /// Computes the content hash of an input in the corpus, this is the same
+    /// hash used to name inputs on disk
+    pub fn content_hash(&self, idx: usize) -> Option<u64> {
+        let input = self.get_input(idx)?;
+
+        let mut hasher = DefaultHasher::new();
+        input.hash(&mut hasher);
+        Some(hasher.finish())
+    }
+
+    /// Retrieves the number of edges in an input's cached coverage, None if
+    /// the input doesn't exist or its coverage hasn't been computed
+    pub fn coverage_size(&self, idx: usize) -> Option<usize> {
+        self.metas
+            .get(idx)?
+            .coverage
+            .as_ref()
+            .map(|coverage| coverage.len())
+    }
+
+    /// Tags an input with its format, ie "png" or "json", so that tag-aware
+    /// splicing can keep grafts between inputs of the same format. Returns
+    /// false if `idx` is out of range
+    #[allow(dead_code)]
+    pub fn set_tag(&mut self, idx: usize, tag: &str) -> bool {
+        let Some(meta) = self.metas.get_mut(idx) else {
+            return false;
+        };
+
+        meta.tag = Some(tag.to_string());
+        true
+    }
+
+    /// Retrieves an input's format tag, None if it's untagged or `idx` is out
+    /// of range
+    pub fn tag(&self, idx: usize) -> Option<&str> {
+        self.metas.get(idx)?.tag.as_deref()
+    }
+
+    /// Assigns an input a selection priority from an external source (ie a
+    /// static analysis pass or a model), used by `Selection::Priority`.
+    /// Negative and NaN scores are treated as 0.0. Returns false if `idx` is
+    /// out of range
+    #[allow(dead_code)]
+    pub fn set_priority(&mut self, idx: usize, score: f64) -> bool {
+        let Some(meta) = self.metas.get_mut(idx) else {
+            return false;
+        };
+
+        meta.priority = Some(score.max(0.0));
+        self.version += 1;
+        true
+    }
+
+    /// Retrieves an input's selection priority, None if one was never set or
+    /// `idx` is out of range
+    pub fn priority(&self, idx: usize) -> Option<f64> {
+        self.metas.get(idx)?.priority
+    }
+
+    /// Adds the bookkeeping for a newly pushed input, it gets the next stable
+    /// id and the corpus version is bumped
+    fn push_meta(&mut self) {
+        self.clock += 1;
+        self.metas.push(InputMeta {
+            id: self.next_input_id,
+            last_used: self.clock,
+            ..Default::default()
+        });
+        self.next_input_id += 1;
+        self.version += 1;
+    }
+
+    /// Caps how many bytes of inputs the in-memory corpus can hold, inputs from
+    /// `add_input`, `save_input`, or a sync that would go over are kept out of
+    /// memory. If `evict_lru` is set the least recently used inputs are evicted
+    /// to make room instead, see `touch`. Inputs given to `save_input` are
+    /// written to disk either way. None removes the limit
+    pub fn set_memory_limit(&mut self, limit: Option<usize>, evict_lru: bool) {
+        self.mem_limit = limit;
+        self.evict_lru = evict_lru;
+    }
+
+    /// Marks an input as used, ie it was just picked for mutation, so that it's
+    /// the last to be evicted under a memory limit
+    pub fn touch(&mut self, idx: usize) {
+        self.clock += 1;
+        if let Some(meta) = self.metas.get_mut(idx) {
+            meta.last_used = self.clock;
+        }
+    }
+
+    /// Makes sure `len` more bytes fit under the memory limit, evicting least
+    /// recently used inputs if that's enabled. Returns false if they don't fit
+    fn make_room(&mut self, len: usize) -> bool {
+        let Some(limit) = self.mem_limit else {
+            return true;
+        };
+
+        // Nothing we evict will make room for an input bigger than the limit
+        if len > limit {
+            return false;
+        }
+
+        while self.corpus_size + len > limit {
+            if !self.evict_lru {
+                return false;
+            }
+
+            let Some(lru) = (0..self.metas.len()).min_by_key(|&idx| self.metas[idx].last_used)
+            else {
+                return false;
+            };
+            self.remove_input(lru);
+        }
+
+        true
+    }
+
+    /// Retrieves the corpus version, it goes up every time inputs are added,
+    /// removed, or reordered, or their coverage or priority changes, through
+    /// the Corpus methods, so anything caching per-input data can cheaply tell
+    /// that it's stale. Changes made directly to `inputs` or `metas` aren't
+    /// tracked
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Retrieves the stable id of the input at `idx`, unlike an index the id
+    /// doesn't change when other inputs are removed or the corpus is shuffled
+    pub fn id_of(&self, idx: usize) -> Option<u64> {
+        self.metas.get(idx).map(|meta| meta.id)
+    }
+
+    /// Finds the current index of the input with the stable id `id`, None if
+    /// that input has been removed
+    #[allow(dead_code)]
+    pub fn index_of_id(&self, id: u64) -> Option<usize> {
+        self.metas.iter().position(|meta| meta.id == id)
+    }
+
+    /// Summarizes the in-memory corpus in a single pass
+    #[allow(dead_code)]
+    pub fn stats(&self) -> CorpusStats {
+        let mut summary = CorpusStats {
+            min_len: usize::MAX,
+            ..Default::default()
+        };
+
+        for (input, meta) in self.inputs.iter().zip(self.metas.iter()) {
+            summary.count += 1;
+            summary.total_bytes += input.len();
+            summary.min_len = std::cmp::min(summary.min_len, input.len());
+            summary.max_len = std::cmp::max(summary.max_len, input.len());
+            summary.favored += meta.favored as usize;
+            summary.covered += meta.coverage.is_some() as usize;
+        }
+
+        // Empty corpus, no lengths to speak of
+        if summary.count == 0 {
+            summary.min_len = 0;
+            return summary;
+        }
+
+        summary.avg_len = summary.total_bytes as f64 / summary.count as f64;
+        summary
+    }
+
+    /// Sets a hook that maps inputs to a canonical form before they're hashed
+    /// for dedup, inputs that canonicalize the same are treated as duplicates
+    /// but the original bytes are what get stored. The hashes of the inputs
+    /// already in memory are recomputed with the new hook
+    #[allow(dead_code)]
+    pub fn set_canonicalizer(&mut self, f: Box<CanonFn>) {
+        self.canon = Some(Arc::from(f));
+
+        let hashes = self
+            .inputs
+            .iter()
+            .map(|input| self.dedup_hash(input))
+            .collect();
+        self.input_hashes = hashes;
+    }
+
+    /// Hashes an input for the dedup database, this is the content hash unless
+    /// a canonicalizer has been set
+    fn dedup_hash(&self, input: &[u8]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        match &self.canon {
+            Some(canon) => canon(input).hash(&mut hasher),
+            None => input.hash(&mut hasher),
+        }
+        hasher.finish()
+    }
+
+    /// Adds an input to the in-memory corpus only, nothing is written to disk.
+    /// Returns false if the input is a duplicate of one we already have or it
+    /// doesn't fit under the memory limit
+    pub fn add_input(&mut self, input: Vec<u8>) -> bool {
+        // Create a hash for the input data
+        let hash = self.dedup_hash(&input);
+
+        // Check the database for a duplicate
+        if self.input_hashes.contains(&hash) {
+            return false;
+        }
+
+        // Check that it fits under the memory limit
+        if !self.make_room(input.len()) {
+            return false;
+        }
+        self.input_hashes.insert(hash);
+
+        // New inputs start out without any cached coverage
+        self.corpus_size += input.len();
+        self.inputs.push(input);
+        self.push_meta();
+        self.index_input(self.inputs.len() - 1);
+
+        true
+    }
+
+    /// Adds a batch of inputs to the in-memory corpus in one pass, the batch is
+    /// deduplicated against the existing inputs and against itself. Returns a
+    /// list parallel to `inputs` holding the index each input landed at, or
+    /// `usize::MAX` for the inputs that were dropped. If LRU eviction kicks in
+    /// partway through, the indices handed out earlier may have shifted
+    pub fn add_inputs(&mut self, inputs: Vec<Vec<u8>>) -> Vec<usize> {
+        self.reserve(inputs.len());
+
+        inputs
+            .into_iter()
+            .map(|input| {
+                if self.add_input(input) {
+                    self.inputs.len() - 1
+                } else {
+                    usize::MAX
+                }
+            })
+            .collect()
+    }
+
+    /// Removes an input from the in-memory corpus, its cached coverage goes
+    /// with it. Inputs after `idx` shift down by one
+    pub fn remove_input(&mut self, idx: usize) -> Option<Vec<u8>> {
+        if idx >= self.inputs.len() {
+            return None;
+        }
+
+        // Remove the hash from the database so the input can be re-added
+        let hash = self.dedup_hash(&self.inputs[idx]);
+        self.input_hashes.remove(&hash);
+
+        let input = self.inputs.remove(idx);
+        self.metas.remove(idx);
+        self.corpus_size -= input.len();
+        self.version += 1;
+
+        // Every id after `idx` shifted, so the index has to be rebuilt
+        if let Some(n) = self.ngrams.as_ref().map(|index| index.n) {
+            self.build_ngram_index(n);
+        }
+
+        Some(input)
+    }
+
+    /// Randomizes the order of the in-memory inputs with a Fisher-Yates shuffle
+    /// driven by `rng`, so the same RNG stream always gives the same order.
+    /// Each input's bookkeeping moves along with it
+    #[allow(dead_code)]
+    pub fn shuffle(&mut self, rng: &mut impl FnMut() -> usize) {
+        for i in (1..self.inputs.len()).rev() {
+            let j = rng() % (i + 1);
+            self.inputs.swap(i, j);
+            self.metas.swap(i, j);
+        }
+        self.version += 1;
+
+        // The n-gram index refers to inputs by position
+        if let Some(n) = self.ngrams.as_ref().map(|index| index.n) {
+            self.build_ngram_index(n);
+        }
+    }
+
+    /// Deterministically partitions the inputs, along with their bookkeeping,
+    /// into two in-memory only corpora for offline evaluation. `ratio` is the
+    /// share of inputs that go to the first (train) corpus, the rest go to the
+    /// second (holdout) corpus. The same `seed` always produces the same
+    /// partition of the same corpus, and inputs keep their relative order
+    #[allow(dead_code)]
+    pub fn split(&self, ratio: f32, seed: usize) -> (Corpus, Corpus) {
+        // Order the inputs by a hash of the seed and their index
+        let mut order: Vec<usize> = (0..self.inputs.len()).collect();
+        order.sort_by_cached_key(|&idx| {
+            let mut hasher = DefaultHasher::new();
+            (seed, idx).hash(&mut hasher);
+            hasher.finish()
+        });
+
+        let num_train = (self.inputs.len() as f32 * ratio.clamp(0.0, 1.0)).round() as usize;
+        let (train, holdout) = order.split_at(num_train);
+
+        (self.subset(train), self.subset(holdout))
+    }
+
+    /// Copies the inputs at `indices` and their bookkeeping into a new in-memory
+    /// only Corpus, the dedup hook, the id counter, the LRU clock, and the
+    /// n-gram index carry over
+    fn subset(&self, indices: &[usize]) -> Corpus {
+        let mut indices = indices.to_vec();
+        indices.sort_unstable();
+
+        let mut corpus = Corpus::with_capacity(indices.len());
+        corpus.canon = self.canon.clone();
+        corpus.next_input_id = self.next_input_id;
+        corpus.clock = self.clock;
+
+        for idx in indices {
+            let input = self.inputs[idx].clone();
+            let hash = corpus.dedup_hash(&input);
+            corpus.input_hashes.insert(hash);
+            corpus.corpus_size += input.len();
+            corpus.inputs.push(input);
+            corpus.metas.push(self.metas[idx].clone());
+        }
+
+        if let Some(index) = self.ngrams.as_ref() {
+            corpus.build_ngram_index(index.n);
+        }
+
+        corpus
+    }
+
+    /// Builds an index from every byte n-gram of length `n` to the inputs that
+    /// contain it, once built the index is kept up to date as inputs are added
+    /// and removed. An `n` of 0 drops the index
+    pub fn build_ngram_index(&mut self, n: usize) {
+        if n == 0 {
+            self.ngrams = None;
+            return;
+        }
+
+        self.ngrams = Some(NgramIndex {
+            n,
+            donors: HashMap::new(),
+        });
+
+        for idx in 0..self.inputs.len() {
+            self.index_input(idx);
+        }
+    }
+
+    /// Adds an input's n-grams to the index if we have one
+    fn index_input(&mut self, idx: usize) {
+        let Some(index) = self.ngrams.as_mut() else {
+            return;
+        };
+
+        for gram in self.inputs[idx].windows(index.n) {
+            let ids = index.donors.entry(ngram_hash(gram)).or_default();
+
+            // An input can contain the same n-gram many times, only list it once
+            if ids.last() != Some(&idx) {
+                ids.push(idx);
+            }
+        }
+    }
+
+    /// Finds a splice donor that shares at least one n-gram with `recipient`
+    /// using the n-gram index, donors are sampled proportionally to how many
+    /// distinct n-grams they share with a single draw from `rng`. At most
+    /// `MAX_RECIPIENT_NGRAMS` of the recipient's n-grams are looked up. Inputs
+    /// identical to the recipient aren't considered. Returns None if there is
+    /// no index or no input shares an n-gram
+    pub fn splice_compatible(
+        &self,
+        recipient: &[u8],
+        rng: &mut dyn FnMut() -> usize,
+    ) -> Option<usize> {
+        let index = self.ngrams.as_ref()?;
+
+        // Sample the recipient's n-grams at an even stride
+        let num_grams = recipient.len().checked_sub(index.n)? + 1;
+        let stride = num_grams.div_ceil(MAX_RECIPIENT_NGRAMS);
+        let grams: HashSet<u64> = recipient
+            .windows(index.n)
+            .step_by(stride)
+            .map(ngram_hash)
+            .collect();
+
+        // Count the distinct n-grams each donor shares with the recipient
+        let mut shared: HashMap<usize, usize> = HashMap::new();
+        for gram in grams {
+            for &idx in index.donors.get(&gram).into_iter().flatten() {
+                *shared.entry(idx).or_insert(0) += 1;
+            }
+        }
+
+        // Order the donors so the same draw always picks the same one
+        let mut donors: Vec<(usize, usize)> = shared
+            .into_iter()
+            .filter(|&(idx, _)| self.inputs[idx] != recipient)
+            .collect();
+        donors.sort_unstable();
+
+        // Roll against the total count and find the donor it lands on
+        let total: usize = donors.iter().map(|&(_, count)| count).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut roll = rng() % total;
+        for &(idx, count) in donors.iter() {
+            if roll < count {
+                return Some(idx);
+            }
+            roll -= count;
+        }
+
+        None
+    }
+
+    /// Makes sure that every input has a cached coverage set, `cov` is only
+    /// invoked for inputs whose cache is empty
+    fn cache_coverage<F>(&mut self, cov: &mut F)
+    where
+        F: FnMut(&[u8]) -> HashSet<u64>,
+    {
+        for (input, meta) in self.inputs.iter().zip(self.metas.iter_mut()) {
+            if meta.coverage.is_none() {
+                meta.coverage = Some(cov(input));
+                self.version += 1;
+            }
+        }
+    }
+
+    /// Marks inputs as favored AFL++ style: for every edge, the smallest input
+    /// that reaches it is favored. Coverage is computed through `cov` only for
+    /// inputs that don't already have a cached coverage set
+    #[allow(dead_code)]
+    pub fn recompute_favored<F>(&mut self, mut cov: F)
+    where
+        F: FnMut(&[u8]) -> HashSet<u64>,
+    {
+        self.cache_coverage(&mut cov);
+
+        // Determine the smallest input for each edge
+        let mut best: HashMap<u64, usize> = HashMap::new();
+        for (idx, meta) in self.metas.iter().enumerate() {
+            for &edge in meta.coverage.as_ref().unwrap() {
+                let entry = best.entry(edge).or_insert(idx);
+                if self.inputs[idx].len() < self.inputs[*entry].len() {
+                    *entry = idx;
+                }
+            }
+        }
+
+        // Update the favored flags
+        self.metas.iter_mut().for_each(|meta| meta.favored = false);
+        for idx in best.into_values() {
+            self.metas[idx].favored = true;
+        }
+    }
+
+    /// Greedily picks a minimal set of input indices that together reach all
+    /// of the coverage the corpus reaches. Coverage is computed through `cov`
+    /// only for inputs that don't already have a cached coverage set
+    #[allow(dead_code)]
+    pub fn minimize_set<F>(&mut self, mut cov: F) -> Vec<usize>
+    where
+        F: FnMut(&[u8]) -> HashSet<u64>,
+    {
+        self.cache_coverage(&mut cov);
+
+        // Everything we need to cover
+        let mut uncovered: HashSet<u64> = self
+            .metas
+            .iter()
+            .flat_map(|meta| meta.coverage.as_ref().unwrap().iter().copied())
+            .collect();
+
+        // Keep taking the input that covers the most uncovered edges
+        let mut minimized = Vec::new();
+        while !uncovered.is_empty() {
+            let (idx, _) = self
+                .metas
+                .iter()
+                .enumerate()
+                .map(|(idx, meta)| {
+                    let coverage = meta.coverage.as_ref().unwrap();
+                    (idx, coverage.intersection(&uncovered).count())
+                })
+                .max_by_key(|&(_, count)| count)
+                .unwrap();
+
+            for edge in self.metas[idx].coverage.as_ref().unwrap() {
+                uncovered.remove(edge);
+            }
+
+            minimized.push(idx);
+        }
+
+        minimized
+    }
+
+    /// Computes the union of every input's coverage, which is a compact
+    /// summary of what the corpus reaches. Cached coverage is used where we
+    /// have it, `cov` is only invoked for inputs without a cached set
+    pub fn coverage_union<F>(&self, mut cov: F) -> HashSet<u64>
+    where
+        F: FnMut(&[u8]) -> HashSet<u64>,
+    {
+        let mut union = HashSet::new();
+        for (input, meta) in self.inputs.iter().zip(self.metas.iter()) {
+            match &meta.coverage {
+                Some(coverage) => union.extend(coverage.iter().copied()),
+                None => union.extend(cov(input)),
+            }
+        }
+
+        union
+    }
+
+    /// Hashes the union of every input's coverage, two corpora that reach the
+    /// same edges have the same fingerprint no matter which inputs reach them
+    #[allow(dead_code)]
+    pub fn coverage_fingerprint<F>(&self, cov: F) -> u64
+    where
+        F: FnMut(&[u8]) -> HashSet<u64>,
+    {
+        // Sets don't have a stable iteration order, so sort before hashing
+        let mut edges: Vec<u64> = self.coverage_union(cov).into_iter().collect();
+        edges.sort_unstable();
+
+        let mut hasher = DefaultHasher::new();
+        edges.hash(&mut hasher);
+        hasher.finish()
+    }
+
     /// Save an input to the corpus
     /// - Hash the input so we can focus on saving only unique inputs
     /// - Attempt to write the input to disk, but fail and warn the user if
@@ -210,12 +1107,31 @@ impl Corpus {
         match std::fs::write(file_path, input) {
             Ok(_) => {
                 self.findings_limit -= input.len();
+
+                // With a canonicalizer the file name hash differs from the
+                // dedup hash so sync has to know we wrote it
+                self.seen_files.insert(hash);
+
+                // Check that it fits under the memory limit, it's on disk
+                // either way
+                if !self.make_room(input.len()) {
+                    finding_warn!(
+                        self.id,
+                        "Corpus memory limit reached, input {:016X} is only on disk",
+                        hash
+                    );
+                    return hash;
+                }
+
                 // Copy the input bytes over in memory only if successfully saved to disk
                 self.inputs.push(input.clone());
+                self.push_meta();
+                self.index_input(self.inputs.len() - 1);
                 self.corpus_size += input.len();
 
                 // Add the hash to the database
-                self.input_hashes.insert(hash);
+                let dedup = self.dedup_hash(input);
+                self.input_hashes.insert(dedup);
             }
             Err(e) => {
                 finding_warn!(self.id, "Unable to save new input to disk, error: {}", e);
@@ -229,6 +1145,8 @@ impl Corpus {
     /// - Hash the crash so we don't duplicate crashes on disk
     /// - Attempt to write the crash to disk, but fail and warn the user if
     /// we have already reached our findings limit
+    /// - If findings are compressed the crash is gzipped and gets a `.gz`
+    ///   suffix, the hash is still of the uncompressed input
     pub fn save_crash(&mut self, input: &Vec<u8>, filetype: &str) -> u64 {
         // Create a hash for the input data
         let mut hasher = DefaultHasher::new();
@@ -236,8 +1154,9 @@ impl Corpus {
         let hash = hasher.finish();
 
         // Create the file path for the new input
-        let file_path =
-            std::path::Path::new(&self.crash_dir).join(format!("{:016X}.{}", hash, filetype));
+        let suffix = if self.compress_findings { ".gz" } else { "" };
+        let file_path = std::path::Path::new(&self.crash_dir)
+            .join(format!("{:016X}.{}{}", hash, filetype, suffix));
         if file_path.exists() {
             finding_warn!(
                 self.id,
@@ -248,8 +1167,21 @@ impl Corpus {
             return hash;
         }
 
+        // Compress the crash if asked to, it's what counts against the limit
+        let data = if self.compress_findings {
+            match gzip(input) {
+                Ok(compressed) => compressed,
+                Err(e) => {
+                    finding_warn!(self.id, "Unable to compress {} input: {}", filetype, e);
+                    return hash;
+                }
+            }
+        } else {
+            input.clone()
+        };
+
         // Make sure we have enough space
-        if input.len() > self.findings_limit {
+        if data.len() > self.findings_limit {
             finding_warn!(
                 self.id,
                 "Unable to save {} input, findings_limit exhausted!",
@@ -259,9 +1191,9 @@ impl Corpus {
         }
 
         // Attempt to save the input to disk
-        match std::fs::write(&file_path, input) {
+        match std::fs::write(&file_path, &data) {
             Ok(_) => {
-                self.findings_limit -= input.len();
+                self.findings_limit -= data.len();
                 // Copy the input bytes over in memory only if successfully saved to disk
                 finding!(
                     self.id,
@@ -287,9 +1219,27 @@ impl Corpus {
     /// Part of the corpus-syncing process, we add a new input that we found
     /// during the sync to the in-memory corpus and update our hash set accordingly
     fn add_new_input(&mut self, hash: u64, content: Vec<u8>) {
+        // With a canonicalizer the file name hash isn't the dedup hash
+        let dedup = self.dedup_hash(&content);
+        if self.input_hashes.contains(&dedup) {
+            return;
+        }
+
+        // Check that it fits under the memory limit
+        if !self.make_room(content.len()) {
+            finding_warn!(
+                self.id,
+                "Corpus memory limit reached, dropping synced input {:016X}",
+                hash
+            );
+            return;
+        }
+        self.input_hashes.insert(dedup);
+
         self.inputs.push(content.clone());
+        self.push_meta();
+        self.index_input(self.inputs.len() - 1);
         self.corpus_size += content.len();
-        self.input_hashes.insert(hash);
 
         finding!(
             self.id,
@@ -303,12 +1253,15 @@ impl Corpus {
     /// Part of the corpus-syncing process, attempt to read the content of a
     /// file in the corpus
     fn process_input_file(&mut self, hash: u64, path: &std::path::Path) {
-        if self.input_hashes.contains(&hash) {
+        if self.input_hashes.contains(&hash) || self.seen_files.contains(&hash) {
             return;
         }
 
         match std::fs::read(path) {
-            Ok(content) => self.add_new_input(hash, content),
+            Ok(content) => {
+                self.seen_files.insert(hash);
+                self.add_new_input(hash, content)
+            }
             Err(e) => finding_warn!(self.id, "Failed to read input file {:016X}: {}", hash, e),
         }
     }
@@ -391,3 +1344,527 @@ impl Corpus {
         self.last_sync = Instant::now();
     }
 }
+
+impl CorpusView for Corpus {
+    fn num_inputs(&self) -> usize {
+        Corpus::num_inputs(self)
+    }
+
+    fn get_input(&self, idx: usize) -> Option<&[u8]> {
+        Corpus::get_input(self, idx)
+    }
+
+    fn content_hash(&self, idx: usize) -> Option<u64> {
+        Corpus::content_hash(self, idx)
+    }
+
+    fn splice_compatible(&self, recipient: &[u8], rng: &mut dyn FnMut() -> usize) -> Option<usize> {
+        Corpus::splice_compatible(self, recipient, rng)
+    }
+
+    fn coverage_size(&self, idx: usize) -> Option<usize> {
+        Corpus::coverage_size(self, idx)
+    }
+
+    fn tag(&self, idx: usize) -> Option<&str> {
+        Corpus::tag(self, idx)
+    }
+
+    fn priority(&self, idx: usize) -> Option<f64> {
+        Corpus::priority(self, idx)
+    }
+
+    fn version(&self) -> Option<u64> {
+        Some(Corpus::version(self))
+    }
+}
+
+/// Writes an input to a fixed path for harnesses that exec the target on a
+/// file (ie AFL++'s `.cur_input`). The data goes to a temporary file next to
+/// `path` that is then renamed over it, so a target reading `path` never sees
+/// a partially written input
+#[allow(dead_code)]
+pub fn write_cur_input(path: &std::path::Path, data: &[u8]) -> Result<(), LucidErr> {
+    // The temporary has to be in the same directory for the rename to be atomic
+    let Some(name) = path.file_name() else {
+        return Err(LucidErr::Corpus(format!(
+            "Invalid current input path '{}'",
+            path.display()
+        )));
+    };
+    let tmp_path = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let result = std::fs::write(&tmp_path, data).and_then(|_| std::fs::rename(&tmp_path, path));
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(LucidErr::Corpus(format!(
+            "Unable to write current input '{}', error: {}",
+            path.display(),
+            e
+        )));
+    }
+
+    Ok(())
+}
+
+/// Parse the size field of a tar header, which is either NUL/space terminated
+/// octal ASCII or, for large GNU entries, big-endian base-256 with the high bit
+/// of the first byte set
+fn parse_tar_size(field: &[u8]) -> Option<usize> {
+    // Base-256
+    if field[0] & 0x80 != 0 {
+        let mut size: usize = (field[0] & 0x7F) as usize;
+        for &byte in &field[1..] {
+            size = size.checked_mul(256)?.checked_add(byte as usize)?;
+        }
+
+        return Some(size);
+    }
+
+    // Octal
+    let digits = std::str::from_utf8(field).ok()?;
+    let digits = digits.trim_matches(|c: char| c == '\0' || c == ' ');
+    if digits.is_empty() {
+        return Some(0);
+    }
+
+    usize::from_str_radix(digits, 8).ok()
+}
+
+/// Compresses `data` into a gzip stream
+fn gzip(data: &[u8]) -> Result<Vec<u8>, LucidErr> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+/// Decompresses a whole gzip stream
+fn gunzip(data: &[u8]) -> Result<Vec<u8>, LucidErr> {
+    let mut decompressed = Vec::new();
+    GzDecoder::new(data).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+/// Hashes a byte n-gram for the n-gram index
+pub fn ngram_hash(gram: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    gram.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates an empty scratch directory unique to the calling test
+    fn scratch_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("lucid-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Builds a tar archive with a regular file entry for each input
+    fn build_tar(inputs: &[&[u8]]) -> Vec<u8> {
+        let mut tar = Vec::new();
+        for (i, input) in inputs.iter().enumerate() {
+            let mut header = [0u8; 512];
+            let name = format!("input{}", i);
+            header[..name.len()].copy_from_slice(name.as_bytes());
+            let size = format!("{:011o}", input.len());
+            header[124..135].copy_from_slice(size.as_bytes());
+            header[156] = b'0';
+            tar.extend_from_slice(&header);
+            tar.extend_from_slice(input);
+            tar.resize(tar.len().div_ceil(512) * 512, 0);
+        }
+
+        // End of archive marker
+        tar.extend_from_slice(&[0u8; 1024]);
+        tar
+    }
+
+    #[test]
+    fn archive_loads_tar_and_tar_gz() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let dir = scratch_dir("archive");
+        let tar = build_tar(&[b"first", b"second"]);
+
+        let path = dir.join("seeds.tar");
+        std::fs::write(&path, &tar).unwrap();
+        let corpus = Corpus::load_from_archive(&path).unwrap();
+        assert_eq!(corpus.inputs, vec![b"first".to_vec(), b"second".to_vec()]);
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&tar).unwrap();
+        let path = dir.join("seeds.tar.gz");
+        std::fs::write(&path, encoder.finish().unwrap()).unwrap();
+        let corpus = Corpus::load_from_archive(&path).unwrap();
+        assert_eq!(corpus.inputs, vec![b"first".to_vec(), b"second".to_vec()]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn gz_dir_round_trip() {
+        let dir = scratch_dir("gz-round-trip");
+        let corpus = Corpus::from_inputs(vec![
+            b"first".to_vec(),
+            vec![0x41; 4096],
+            (0..=255).collect(),
+        ]);
+
+        assert_eq!(corpus.save_to_dir_gz(&dir).unwrap(), 3);
+        let loaded = Corpus::load_from_dir_gz(&dir).unwrap();
+
+        let mut expected = corpus.inputs.clone();
+        let mut actual = loaded.inputs.clone();
+        expected.sort();
+        actual.sort();
+        assert_eq!(expected, actual);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn compressed_crash_is_gzipped() {
+        let dir = scratch_dir("gz-crash");
+        let mut corpus = Corpus::with_capacity(0);
+        corpus.crash_dir = dir.to_string_lossy().to_string();
+        corpus.findings_limit = usize::MAX;
+        corpus.compress_findings = true;
+
+        let input = vec![0x41; 4096];
+        let hash = corpus.save_crash(&input, "crash");
+        let saved = std::fs::read(dir.join(format!("{:016X}.crash.gz", hash))).unwrap();
+        assert!(saved.len() < input.len());
+        assert_eq!(gunzip(&saved).unwrap(), input);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn archive_with_huge_size_is_rejected() {
+        let dir = scratch_dir("archive-huge");
+        let mut tar = build_tar(&[b"first"]);
+
+        // A base-256 size right at the top of the address space
+        tar[124] = 0x80;
+        tar[125..128].fill(0x00);
+        tar[128..136].fill(0xFF);
+
+        let path = dir.join("seeds.tar");
+        std::fs::write(&path, &tar).unwrap();
+        assert!(matches!(
+            Corpus::load_from_archive(&path),
+            Err(LucidErr::Corpus(_))
+        ));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn corpus_with_canonicalizer_is_send() {
+        let mut corpus = Corpus::from_inputs(vec![b"Aone".to_vec()]);
+        corpus.set_canonicalizer(Box::new(|input: &[u8]| input[1..].to_vec()));
+
+        let corpus = std::thread::spawn(move || {
+            corpus.add_input(b"Bone".to_vec());
+            corpus
+        })
+        .join()
+        .unwrap();
+        assert_eq!(corpus.num_inputs(), 1);
+    }
+
+    #[test]
+    fn sync_reads_each_file_once_with_canonicalizer() {
+        let dir = scratch_dir("sync-once");
+        let mut corpus = Corpus::with_capacity(0);
+        corpus.set_canonicalizer(Box::new(|input: &[u8]| input[1..].to_vec()));
+
+        // The file name hash never matches a canonical hash
+        let path = dir.join("00000000DEADBEEF.input");
+        std::fs::write(&path, b"Aone").unwrap();
+        corpus.process_input_file(0xDEADBEEF, &path);
+        assert_eq!(corpus.num_inputs(), 1);
+
+        // A second pass over the same file name doesn't read it again
+        std::fs::write(&path, b"Atwo").unwrap();
+        corpus.process_input_file(0xDEADBEEF, &path);
+        assert_eq!(corpus.num_inputs(), 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn splice_donors_are_sampled_by_shared_ngrams() {
+        let mut corpus = Corpus::from_inputs(vec![
+            b"hello there".to_vec(),
+            b"xxxxorld".to_vec(),
+            b"zzzzzzzz".to_vec(),
+            b"hello world".to_vec(),
+        ]);
+        corpus.build_ngram_index(3);
+
+        let mut state = 0x1337;
+        let mut rng = || crate::mutator::core_mutations::xorshift(&mut state);
+        let mut picks = [0usize; 4];
+        for _ in 0..1000 {
+            let idx = corpus.splice_compatible(b"hello world", &mut rng).unwrap();
+            picks[idx] += 1;
+        }
+
+        // Both donors that share n-grams get picked, the one sharing more is
+        // picked more often, and the recipient itself never is
+        assert!(picks[0] > picks[1] && picks[1] > 0);
+        assert_eq!(picks[2], 0);
+        assert_eq!(picks[3], 0);
+    }
+
+    #[test]
+    fn saved_inputs_respect_memory_limit() {
+        let dir = scratch_dir("save-limit");
+        let mut corpus = Corpus::with_capacity(0);
+        corpus.inputs_dir = dir.to_string_lossy().to_string();
+        corpus.findings_limit = usize::MAX;
+
+        // Without eviction the newest input only makes it to disk
+        corpus.set_memory_limit(Some(8), false);
+        corpus.save_input(&b"aaaa".to_vec());
+        corpus.save_input(&b"bbbb".to_vec());
+        corpus.save_input(&b"cccc".to_vec());
+        assert_eq!(corpus.inputs, vec![b"aaaa".to_vec(), b"bbbb".to_vec()]);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 3);
+
+        // With eviction the least recently used input makes room
+        corpus.set_memory_limit(Some(8), true);
+        corpus.touch(0);
+        corpus.save_input(&b"dddd".to_vec());
+        assert_eq!(corpus.inputs, vec![b"aaaa".to_vec(), b"dddd".to_vec()]);
+        assert!(corpus.corpus_size <= 8);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn favored_reuses_cached_coverage() {
+        let mut corpus = Corpus::from_inputs(vec![b"AB".to_vec(), b"A".to_vec(), b"B".to_vec()]);
+
+        // An input covers one edge per distinct byte
+        let calls = std::cell::Cell::new(0);
+        let cov = |input: &[u8]| {
+            calls.set(calls.get() + 1);
+            input
+                .iter()
+                .map(|&byte| byte as u64)
+                .collect::<HashSet<u64>>()
+        };
+
+        corpus.recompute_favored(cov);
+        corpus.recompute_favored(cov);
+        assert_eq!(calls.get(), 3);
+
+        // "A" and "B" are the smallest inputs reaching their edges
+        let favored: Vec<bool> = corpus.metas.iter().map(|meta| meta.favored).collect();
+        assert_eq!(favored, [false, true, true]);
+
+        // Only the new input needs its coverage computed
+        corpus.add_input(b"C".to_vec());
+        corpus.recompute_favored(cov);
+        assert_eq!(calls.get(), 4);
+    }
+
+    #[test]
+    fn stats_summarizes_input_lengths() {
+        let corpus =
+            Corpus::from_inputs(vec![b"a".to_vec(), b"abcd".to_vec(), b"abcdefg".to_vec()]);
+        let summary = corpus.stats();
+
+        assert_eq!(summary.count, 3);
+        assert_eq!(summary.total_bytes, 12);
+        assert_eq!(summary.min_len, 1);
+        assert_eq!(summary.max_len, 7);
+        assert_eq!(summary.avg_len, 4.0);
+        assert_eq!(summary.favored, 0);
+        assert_eq!(summary.covered, 0);
+    }
+
+    #[test]
+    fn shuffle_keeps_metadata_paired() {
+        let mut corpus = Corpus::from_inputs(vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()]);
+        corpus.set_tag(0, "A");
+        corpus.set_tag(1, "B");
+        corpus.set_tag(2, "C");
+
+        // Fisher-Yates draws for i = 2 then i = 1, swap(2, 0) then swap(1, 0)
+        let mut draws = [0, 0].into_iter();
+        corpus.shuffle(&mut || draws.next().unwrap());
+        assert_eq!(corpus.inputs, [b"b".to_vec(), b"c".to_vec(), b"a".to_vec()]);
+
+        for (input, meta) in corpus.inputs.iter().zip(corpus.metas.iter()) {
+            assert_eq!(
+                meta.tag.as_deref().unwrap().as_bytes(),
+                input.to_ascii_uppercase()
+            );
+        }
+    }
+
+    #[test]
+    fn with_capacity_reserves_up_front() {
+        let mut corpus = Corpus::with_capacity(1000);
+        assert!(corpus.inputs.capacity() >= 1000);
+        assert!(corpus.metas.capacity() >= 1000);
+        assert_eq!(corpus.num_inputs(), 0);
+
+        corpus.add_input(b"lucid".to_vec());
+        corpus.reserve(5000);
+        assert!(corpus.inputs.capacity() >= 5001);
+        assert!(corpus.metas.capacity() >= 5001);
+    }
+
+    #[test]
+    fn add_inputs_dedups_the_batch() {
+        let mut corpus = Corpus::from_inputs(vec![b"a".to_vec()]);
+        let accepted = corpus.add_inputs(vec![
+            b"b".to_vec(),
+            b"a".to_vec(),
+            b"c".to_vec(),
+            b"b".to_vec(),
+        ]);
+
+        assert_eq!(accepted, [1, usize::MAX, 2, usize::MAX]);
+        assert_eq!(corpus.inputs, [b"a".to_vec(), b"b".to_vec(), b"c".to_vec()]);
+    }
+
+    #[test]
+    fn cur_input_is_written_without_leftovers() {
+        let dir = scratch_dir("cur_input");
+        let path = dir.join(".cur_input");
+
+        write_cur_input(&path, b"first").unwrap();
+        write_cur_input(&path, b"second input").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"second input");
+
+        let entries: Vec<_> = std::fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn ids_survive_removals() {
+        let mut corpus = Corpus::from_inputs(vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()]);
+        let ids: Vec<u64> = (0..3).map(|idx| corpus.id_of(idx).unwrap()).collect();
+
+        corpus.remove_input(0);
+        assert_eq!(corpus.id_of(0), Some(ids[1]));
+        assert_eq!(corpus.id_of(1), Some(ids[2]));
+        assert_eq!(corpus.index_of_id(ids[0]), None);
+        assert_eq!(corpus.index_of_id(ids[2]), Some(1));
+
+        // New inputs never reuse an id
+        corpus.add_input(b"d".to_vec());
+        assert!(corpus.id_of(2).unwrap() > ids[2]);
+    }
+
+    #[test]
+    fn version_tracks_changes_only() {
+        let mut corpus = Corpus::from_inputs(vec![b"a".to_vec()]);
+        let version = corpus.version();
+
+        corpus.add_input(b"b".to_vec());
+        assert_eq!(corpus.version(), version + 1);
+
+        corpus.remove_input(0);
+        assert_eq!(corpus.version(), version + 2);
+
+        // Reads leave it alone
+        let _ = corpus.get_input(0);
+        let _ = corpus.stats();
+        let _ = corpus.id_of(0);
+        assert_eq!(corpus.version(), version + 2);
+    }
+
+    #[test]
+    fn canonicalizer_dedups_on_zeroed_ranges() {
+        let mut corpus = Corpus::with_capacity(0);
+        corpus.set_canonicalizer(Box::new(|input: &[u8]| {
+            let mut canon = input.to_vec();
+            canon[4..8].fill(0);
+            canon
+        }));
+
+        corpus.add_input(b"HDR:1111:body".to_vec());
+        corpus.add_input(b"HDR:2222:body".to_vec());
+        corpus.add_input(b"HDR:2222:tail".to_vec());
+
+        assert_eq!(corpus.num_inputs(), 2);
+        assert_eq!(corpus.get_input(0), Some(&b"HDR:1111:body"[..]));
+    }
+
+    #[test]
+    fn same_coverage_has_the_same_fingerprint() {
+        // Each input covers the edges named by its bytes
+        let cov = |input: &[u8]| input.iter().map(|&byte| byte as u64).collect();
+
+        let a = Corpus::from_inputs(vec![vec![1, 2], vec![3]]);
+        let b = Corpus::from_inputs(vec![vec![3, 2], vec![1, 1]]);
+        let c = Corpus::from_inputs(vec![vec![1, 2]]);
+
+        assert_eq!(a.coverage_union(cov), HashSet::from([1, 2, 3]));
+        assert_eq!(a.coverage_fingerprint(cov), b.coverage_fingerprint(cov));
+        assert_ne!(a.coverage_fingerprint(cov), c.coverage_fingerprint(cov));
+    }
+
+    #[test]
+    fn split_is_sized_by_ratio_and_reproducible() {
+        let mut corpus = Corpus::from_inputs((0..100u8).map(|i| vec![i]).collect());
+        corpus.set_tag(7, "seven");
+
+        let (train, holdout) = corpus.split(0.8, 0x1337);
+        assert_eq!(train.num_inputs(), 80);
+        assert_eq!(holdout.num_inputs(), 20);
+
+        let (again, _) = corpus.split(0.8, 0x1337);
+        assert_eq!(train.inputs, again.inputs);
+        let (other, _) = corpus.split(0.8, 0x4141);
+        assert_ne!(train.inputs, other.inputs);
+
+        // Metadata goes wherever its input went
+        let (with_seven, idx) = if let Some(idx) = train.inputs.iter().position(|i| i == &[7]) {
+            (&train, idx)
+        } else {
+            let idx = holdout.inputs.iter().position(|i| i == &[7]).unwrap();
+            (&holdout, idx)
+        };
+        assert_eq!(with_seven.tag(idx), Some("seven"));
+    }
+
+    #[test]
+    fn add_input_honors_the_byte_ceiling() {
+        let mut corpus = Corpus::with_capacity(0);
+        corpus.set_memory_limit(Some(10), false);
+
+        assert!(corpus.add_input(b"aaaa".to_vec()));
+        assert!(corpus.add_input(b"bbbb".to_vec()));
+        assert!(!corpus.add_input(b"cccc".to_vec()));
+        assert_eq!(corpus.num_inputs(), 2);
+
+        // Evicting makes room by dropping the least recently used input
+        corpus.set_memory_limit(Some(10), true);
+        corpus.touch(0);
+        assert!(corpus.add_input(b"cccc".to_vec()));
+        assert_eq!(corpus.inputs, vec![b"aaaa".to_vec(), b"cccc".to_vec()]);
+
+        // An input bigger than the whole ceiling never fits
+        assert!(!corpus.add_input(vec![0x41; 11]));
+    }
+}
@@ -1,5 +1,7 @@
 //! This file contains all of the logic pertaining to code coverage feedback
 
+use crate::misc::bitmap_density;
+
 /// This is the size of coverage map, this *has* to be a power of 2
 const COVERAGE_MAP_SIZE: usize = 65536;
 
@@ -68,6 +70,26 @@ impl CoverageMap {
         edge_count
     }
 
+    /// Fraction of the historical hit count bucket bits that are set
+    pub fn density(&self) -> f32 {
+        bitmap_density(&self.history_map)
+    }
+
+    /// Reports whether curr_map has a new bucket value for any edge pair like
+    /// `update_coverage` would, but neither map is changed
+    pub fn has_new_coverage(&self) -> bool {
+        self.curr_map
+            .iter()
+            .zip(self.history_map.iter())
+            .any(|(&curr, &hist)| curr > 0 && hist < CoverageMap::bucket(curr))
+    }
+
+    /// Zeroes out the curr_map for the next fuzzing iteration without
+    /// recording any of it in the history_map
+    pub fn discard_coverage(&mut self) {
+        self.curr_map.fill(0);
+    }
+
     /// Walks the curr_map and the history_map concurrently comparing the two
     /// values at each index, if curr_map has a new bucket value for a specific
     /// edge pair we return that we found new coverage. curr_map is zeroed out
@@ -1,19 +1,83 @@
 //! A place-holder for perhaps a more detailed/robust error reporting system in
 //! the future
 
-#[derive(Debug, Clone)]
-pub struct LucidErr {
-    message: String,
+use std::fmt;
+
+/// Errors are split into rough categories so callers can match on where
+/// something went wrong, most of the codebase just uses the generic message
+#[derive(Debug)]
+pub enum LucidErr {
+    Generic(String),    // Anything without a more specific category
+    Mutator(String),    // Mutator construction or mutation failures
+    Corpus(String),     // Corpus loading, saving, and bookkeeping failures
+    Io(std::io::Error), // Underlying I/O failures
 }
 
 impl LucidErr {
     pub fn from(message: &str) -> Self {
-        LucidErr {
-            message: message.to_string(),
-        }
+        LucidErr::Generic(message.to_string())
     }
 
     pub fn display(&self) {
-        println!("{}", self.message);
+        println!("{}", self);
+    }
+}
+
+impl fmt::Display for LucidErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LucidErr::Generic(message) => write!(f, "{}", message),
+            LucidErr::Mutator(message) => write!(f, "Mutator error: {}", message),
+            LucidErr::Corpus(message) => write!(f, "Corpus error: {}", message),
+            LucidErr::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+/// io::Error isn't Clone, so an I/O error is cloned as a new error with the
+/// same kind and message
+impl Clone for LucidErr {
+    fn clone(&self) -> Self {
+        match self {
+            LucidErr::Generic(message) => LucidErr::Generic(message.clone()),
+            LucidErr::Mutator(message) => LucidErr::Mutator(message.clone()),
+            LucidErr::Corpus(message) => LucidErr::Corpus(message.clone()),
+            LucidErr::Io(e) => LucidErr::Io(std::io::Error::new(e.kind(), e.to_string())),
+        }
+    }
+}
+
+impl From<&str> for LucidErr {
+    fn from(message: &str) -> Self {
+        LucidErr::Generic(message.to_string())
+    }
+}
+
+impl From<String> for LucidErr {
+    fn from(message: String) -> Self {
+        LucidErr::Generic(message)
+    }
+}
+
+impl From<std::io::Error> for LucidErr {
+    fn from(e: std::io::Error) -> Self {
+        LucidErr::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_errors_keep_their_category() {
+        let e = std::io::Error::new(std::io::ErrorKind::NotFound, "no such seed");
+        let err: LucidErr = e.into();
+        assert!(matches!(err, LucidErr::Io(_)));
+        assert_eq!(err.to_string(), "I/O error: no such seed");
+
+        // Clones keep the category too
+        assert!(matches!(err.clone(), LucidErr::Io(_)));
+        assert!(matches!(LucidErr::from("oops"), LucidErr::Generic(_)));
     }
 }
@@ -12,6 +12,7 @@ mod loader;
 mod misc;
 mod mmu;
 mod mutator;
+mod packed;
 mod redqueen;
 mod snapshot;
 mod stats;
@@ -111,7 +112,10 @@ fn main() {
     prompt!("Scratch RSP @ 0x{:X}", lucid_context.scratch_rsp);
 
     // Update user with Mutator details
-    prompt!("Mutator seeded with 0x{:X}", lucid_context.mutator.rng);
+    prompt!(
+        "Mutator seeded with 0x{:X}",
+        lucid_context.mutator.rng_state()
+    );
     prompt!(
         "Mutator max input size: 0x{:X}",
         lucid_context.mutator.max_size
@@ -161,8 +165,9 @@ fn main() {
 
         // Check how many edges we found
         prompt!(
-            "Seeds found {} edge(s)",
-            lucid_context.coverage.get_edge_count()
+            "Seeds found {} edge(s), map density {:.4}%",
+            lucid_context.coverage.get_edge_count(),
+            lucid_context.coverage.density() * 100.0
         );
     }
 
@@ -190,6 +195,9 @@ fn main() {
         // Fork fuzzers off
         prompt!("Spawning fuzzers...");
         for i in 0..lucid_context.config.num_fuzzers {
+            // Every fuzzer gets its own pRNG stream, derived from ours
+            let child_seed = lucid_context.mutator.advance_for_child();
+
             let fork_result = unsafe { libc::fork() };
 
             if fork_result == -1 {
@@ -202,6 +210,13 @@ fn main() {
                 // terminal with Bochs prints
                 lucid_context.verbose = false;
 
+                // A seeded campaign stays reproducible, otherwise use entropy
+                if lucid_context.config.mutator_seed.is_some() {
+                    lucid_context.mutator.set_rng_state(child_seed);
+                } else {
+                    lucid_context.mutator.reseed();
+                }
+
                 // Pin ourselves to core
                 pin_core(i);
 
@@ -51,6 +51,41 @@ macro_rules! finding_warn {
     });
 }
 
+/// Variants of the prompt macros that write to stderr instead of stdout, so
+/// that diagnostics can be kept separate from output that is being parsed
+#[macro_export]
+macro_rules! eprompt {
+    () => ({
+        eprint!("\x1b[1;35mlucid::\x1b[0m\n");
+    });
+    ($($arg:tt)*) => ({
+        eprint!("\x1b[1;35mlucid::\x1b[0m ");
+        eprintln!($($arg)*);
+    });
+}
+
+#[macro_export]
+macro_rules! eprompt_warn {
+    () => ({
+        eprint!("\x1b[1;33mlucid::\x1b[0m\n");
+    });
+    ($($arg:tt)*) => ({
+        eprint!("\x1b[1;33mlucid::\x1b[0m ");
+        eprintln!($($arg)*);
+    });
+}
+
+#[macro_export]
+macro_rules! efinding_warn {
+    ($id:expr) => ({
+        eprint!("\x1b[1;37mfuzzer-{}:\x1b[0m\n", $id);
+    });
+    ($id:expr, $($arg:tt)*) => ({
+        eprint!("\x1b[1;37mfuzzer-{}:\x1b[0m ", $id);
+        eprintln!($($arg)*);
+    });
+}
+
 #[macro_export]
 macro_rules! fatal {
     ($err:expr) => {{
@@ -92,6 +127,7 @@ macro_rules! clear {
 
 /// A way to display an error message and bypass all of the Rust runtime exit
 /// code before exiting
+#[cfg(target_arch = "x86_64")]
 #[macro_export]
 macro_rules! mega_panic {
     ($msg:expr) => {{
@@ -128,6 +164,36 @@ macro_rules! mega_panic {
     }};
 }
 
+/// On targets without the x86_64 syscall path we use the portable fallback
+#[cfg(not(target_arch = "x86_64"))]
+#[macro_export]
+macro_rules! mega_panic {
+    ($msg:expr) => {
+        $crate::mega_panic_libc!($msg)
+    };
+}
+
+/// Portable fallback for targets without the x86_64 syscall path, `write` and
+/// `_exit` are both async-signal-safe and `_exit` skips the Rust runtime exit
+/// code just the same. It's its own macro so it can be tested on any target
+#[doc(hidden)]
+#[macro_export]
+macro_rules! mega_panic_libc {
+    ($msg:expr) => {{
+        // Length of the message
+        let msg_len = $msg.len();
+        let msg_ptr = $msg.as_ptr();
+
+        unsafe {
+            // Write the message to stderr
+            libc::write(libc::STDERR_FILENO, msg_ptr as *const libc::c_void, msg_len);
+
+            // Exit with an error code
+            libc::_exit(1);
+        }
+    }};
+}
+
 /// Thin wrapper to hide an unsafe function call to retrieve the value of xcr0
 pub fn get_xcr0() -> u64 {
     unsafe { _xgetbv(0) }
@@ -181,9 +247,79 @@ pub fn non_block_waitpid(pid: i32, status: &mut i32) -> i32 {
     unsafe { libc::waitpid(pid, status, libc::WNOHANG) }
 }
 
+/// How bad a finding is based on the signal that was delivered, used to
+/// prioritize triage
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Low,      // Anything that isn't a fault
+    Medium,   // Arithmetic and alignment faults
+    High,     // Aborts and illegal instructions
+    Critical, // Memory access violations
+}
+
+/// Classifies a signal into a severity tier
+pub fn classify_signal(sig: i32) -> Severity {
+    match sig {
+        libc::SIGSEGV => Severity::Critical,
+        libc::SIGABRT | libc::SIGILL => Severity::High,
+        libc::SIGFPE | libc::SIGBUS => Severity::Medium,
+        _ => Severity::Low,
+    }
+}
+
+/// Retrieves the stable name of a signal (ie "SIGSEGV"), unknown signal
+/// numbers are named "UNKNOWN"
+pub fn signal_name(sig: i32) -> &'static str {
+    match sig {
+        libc::SIGHUP => "SIGHUP",
+        libc::SIGINT => "SIGINT",
+        libc::SIGQUIT => "SIGQUIT",
+        libc::SIGILL => "SIGILL",
+        libc::SIGTRAP => "SIGTRAP",
+        libc::SIGABRT => "SIGABRT",
+        libc::SIGBUS => "SIGBUS",
+        libc::SIGFPE => "SIGFPE",
+        libc::SIGKILL => "SIGKILL",
+        libc::SIGUSR1 => "SIGUSR1",
+        libc::SIGSEGV => "SIGSEGV",
+        libc::SIGUSR2 => "SIGUSR2",
+        libc::SIGPIPE => "SIGPIPE",
+        libc::SIGALRM => "SIGALRM",
+        libc::SIGTERM => "SIGTERM",
+        libc::SIGSTKFLT => "SIGSTKFLT",
+        libc::SIGCHLD => "SIGCHLD",
+        libc::SIGCONT => "SIGCONT",
+        libc::SIGSTOP => "SIGSTOP",
+        libc::SIGTSTP => "SIGTSTP",
+        libc::SIGTTIN => "SIGTTIN",
+        libc::SIGTTOU => "SIGTTOU",
+        libc::SIGURG => "SIGURG",
+        libc::SIGXCPU => "SIGXCPU",
+        libc::SIGXFSZ => "SIGXFSZ",
+        libc::SIGVTALRM => "SIGVTALRM",
+        libc::SIGPROF => "SIGPROF",
+        libc::SIGWINCH => "SIGWINCH",
+        libc::SIGIO => "SIGIO",
+        libc::SIGPWR => "SIGPWR",
+        libc::SIGSYS => "SIGSYS",
+        _ => "UNKNOWN",
+    }
+}
+
+/// The non-terminal outcomes of checking on a child with waitpid, stopped and
+/// continued children are only reported if waitpid was called with
+/// `WUNTRACED`/`WCONTINUED`, which a ptrace-based driver will want to do
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WaitOutcome {
+    Running,      // No change in the child's state
+    Stopped(i32), // Child was stopped by the contained signal
+    Continued,    // Child was resumed by SIGCONT
+}
+
 /// Check a waitpid result and return an error if the pid has exited or was
-/// signaled terminally
-pub fn handle_wait_result(result: i32, status: &i32) -> Result<(), ()> {
+/// signaled terminally. Stopped and continued children are not failures, a
+/// ptrace driver can `PTRACE_CONT` a `WaitOutcome::Stopped` child
+pub fn handle_wait_result(result: i32, status: &i32) -> Result<WaitOutcome, ()> {
     match result {
         1.. => {
             if libc::WIFEXITED(*status) {
@@ -192,12 +328,21 @@ pub fn handle_wait_result(result: i32, status: &i32) -> Result<(), ()> {
                 return Err(());
             } else if libc::WIFSIGNALED(*status) {
                 let signal = libc::WTERMSIG(*status);
-                prompt_warn!("Child fuzzer was signaled with: {}", signal);
+                prompt_warn!(
+                    "Child fuzzer was signaled with: {} ({}, {:?} severity)",
+                    signal,
+                    signal_name(signal),
+                    classify_signal(signal)
+                );
                 return Err(());
+            } else if libc::WIFSTOPPED(*status) {
+                return Ok(WaitOutcome::Stopped(libc::WSTOPSIG(*status)));
+            } else if libc::WIFCONTINUED(*status) {
+                return Ok(WaitOutcome::Continued);
             }
 
             // Unknown cause?
-            prompt_warn!("Child fuzzer was stopped, we don't know why");
+            prompt_warn!("Child fuzzer changed state, we don't know why");
             return Err(());
         }
         -1 => {
@@ -207,5 +352,159 @@ pub fn handle_wait_result(result: i32, status: &i32) -> Result<(), ()> {
         _ => (), // No change, good!
     }
 
-    Ok(())
+    Ok(WaitOutcome::Running)
+}
+
+/// ORs the coverage bitmap `from` into `into`, ie to fold a remote worker's
+/// coverage into our own. Only the overlapping prefix of the two bitmaps is
+/// merged. Returns true if any bit was set in `into` that wasn't before
+#[allow(dead_code)]
+pub fn merge_bitmaps(into: &mut [u8], from: &[u8]) -> bool {
+    let mut new_bits = false;
+
+    for (dst, src) in into.iter_mut().zip(from) {
+        if src & !*dst != 0 {
+            new_bits = true;
+        }
+
+        *dst |= src;
+    }
+
+    new_bits
+}
+
+/// Fraction of bits set in a coverage bitmap, 0.0 for an empty bitmap
+pub fn bitmap_density(bitmap: &[u8]) -> f32 {
+    if bitmap.is_empty() {
+        return 0.0;
+    }
+
+    let set: u64 = bitmap.iter().map(|byte| byte.count_ones() as u64).sum();
+    set as f32 / (bitmap.len() * 8) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs `f` in a forked child with stderr redirected into a pipe, returns
+    /// the child's exit code and what it wrote to stderr
+    fn run_child(f: fn()) -> (i32, Vec<u8>) {
+        unsafe {
+            let mut fds = [0i32; 2];
+            assert_eq!(libc::pipe(fds.as_mut_ptr()), 0);
+
+            let pid = libc::fork();
+            assert!(pid >= 0);
+            if pid == 0 {
+                libc::dup2(fds[1], libc::STDERR_FILENO);
+                f();
+                libc::_exit(0);
+            }
+            libc::close(fds[1]);
+
+            let mut output = vec![0u8; 64];
+            let n = libc::read(fds[0], output.as_mut_ptr() as *mut libc::c_void, 64);
+            output.truncate(n.max(0) as usize);
+            libc::close(fds[0]);
+
+            let mut status = 0;
+            libc::waitpid(pid, &mut status, 0);
+            (libc::WEXITSTATUS(status), output)
+        }
+    }
+
+    #[test]
+    fn mega_panic_exits_with_message() {
+        assert_eq!(
+            run_child(|| crate::mega_panic!("mega panic\n")),
+            (1, b"mega panic\n".to_vec())
+        );
+    }
+
+    #[test]
+    fn mega_panic_libc_exits_with_message() {
+        assert_eq!(
+            run_child(|| crate::mega_panic_libc!("mega panic\n")),
+            (1, b"mega panic\n".to_vec())
+        );
+    }
+
+    // Linux packs wait statuses as (sig << 8) | 0x7f for stopped, 0xffff for
+    // continued, (code << 8) for exited and the bare signal for terminated
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn wait_result_reports_stopped_and_continued() {
+        let stopped = (libc::SIGSTOP << 8) | 0x7f;
+        assert_eq!(
+            handle_wait_result(1, &stopped),
+            Ok(WaitOutcome::Stopped(libc::SIGSTOP))
+        );
+
+        let trapped = (libc::SIGTRAP << 8) | 0x7f;
+        assert_eq!(
+            handle_wait_result(1, &trapped),
+            Ok(WaitOutcome::Stopped(libc::SIGTRAP))
+        );
+
+        assert_eq!(handle_wait_result(1, &0xffff), Ok(WaitOutcome::Continued));
+        assert_eq!(handle_wait_result(0, &0), Ok(WaitOutcome::Running));
+        assert!(handle_wait_result(1, &(3 << 8)).is_err());
+        assert!(handle_wait_result(1, &libc::SIGKILL).is_err());
+        assert!(handle_wait_result(-1, &0).is_err());
+    }
+
+    // libtest captures eprint! as well as print!, so the macros are run in a
+    // copy of the test binary with capturing turned off
+    #[test]
+    fn eprompt_writes_to_stderr() {
+        if std::env::var_os("LUCID_EPROMPT_CHILD").is_some() {
+            crate::eprompt!("to stderr");
+            crate::eprompt_warn!("warn to stderr");
+            crate::prompt!("to stdout");
+            return;
+        }
+
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args([
+                "--exact",
+                "misc::tests::eprompt_writes_to_stderr",
+                "--nocapture",
+            ])
+            .env("LUCID_EPROMPT_CHILD", "1")
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert!(stderr.contains("lucid::\x1b[0m to stderr\n"));
+        assert!(stderr.contains("lucid::\x1b[0m warn to stderr\n"));
+        assert!(!stderr.contains("to stdout"));
+        assert!(stdout.contains("to stdout"));
+        assert!(!stdout.contains("to stderr"));
+    }
+
+    #[test]
+    fn signals_are_named_and_classified() {
+        assert_eq!(classify_signal(11), Severity::Critical);
+        assert_eq!(signal_name(11), "SIGSEGV");
+        assert_eq!(classify_signal(libc::SIGABRT), Severity::High);
+        assert_eq!(classify_signal(libc::SIGFPE), Severity::Medium);
+        assert_eq!(classify_signal(libc::SIGTERM), Severity::Low);
+        assert_eq!(signal_name(1234), "UNKNOWN");
+    }
+
+    #[test]
+    fn bitmaps_merge_and_report_density() {
+        let mut into = [0b0000_0001, 0b0000_0000, 0b1111_0000];
+        assert!(merge_bitmaps(&mut into, &[0b0000_0001, 0b1000_0000]));
+        assert_eq!(into, [0b0000_0001, 0b1000_0000, 0b1111_0000]);
+
+        // Nothing new the second time around
+        assert!(!merge_bitmaps(&mut into, &[0b0000_0001, 0b1000_0000]));
+
+        assert_eq!(bitmap_density(&into), 6.0 / 24.0);
+        assert_eq!(bitmap_density(&[0xFF; 4]), 1.0);
+        assert_eq!(bitmap_density(&[]), 0.0);
+    }
 }
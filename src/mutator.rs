@@ -2,7 +2,7 @@
 //! worst mutator of all time, there is no science here
 //!
 //! We get passed a corpus in Mutator, because we need access to other inputs,
-//! Corpus should implement these two methods:
+//! the corpus just has to implement the `CorpusView` trait's two methods:
 //! - num_inputs() -> Returns the number of inputs in the Corpus
 //! - get_input() -> Returns a slice view of an input in the Corpus
 //!
@@ -12,9 +12,19 @@
 //! AFL++ does. Eventually we'll try to just use LibAFL's mutator?
 
 use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
 
-use crate::corpus::Corpus;
+use crate::corpus::{ngram_hash, CorpusView};
+use crate::err::LucidErr;
+use crate::prompt_warn;
+
+pub mod core_mutations;
+
+use core_mutations::{xorshift, ByteBuffer, MAX_BYTE_CORRUPTION};
 
 /// The maximum amount of mutation rounds we can apply to an input, I *think*
 /// this is what AFL++ does?
@@ -29,20 +39,46 @@ const LONGSHOT_MUTATION_RATE: usize = 5;
 /// rather than pull one from the corpus to mutate
 const GEN_SCRATCH_RATE: usize = 1;
 
-/// When mutation strategies rely on mutating a number of bytes, this figure
-/// provides the ceiling for how many bytes they are allowed to corrupt. Keep
-/// in mind that inputs may pass through multiple rounds of mutation.
-const MAX_BYTE_CORRUPTION: usize = 64;
+/// The number of per-call mutation sequences kept in the session history
+const MAX_HISTORY: usize = 256;
+
+/// This percentage is the rate at which a dictionary token with a preferred
+/// offset is placed at that offset rather than somewhere random
+const DICT_PREFERRED_OFFSET_RATE: usize = 90;
+
+/// The number of most frequent byte pairs `learn_bigrams` keeps
+const MAX_BIGRAMS: usize = 32;
+
+/// Length of the byte n-grams `novelty_score` looks at, a harness building its
+/// set of seen n-grams should hash windows of this length with `ngram_hash`
+pub const NOVELTY_NGRAM_LEN: usize = 4;
+
+/// The number of havoc outputs `schedule` yields after the deterministic stages
+/// unless `MutatorConfig::schedule_havoc` says otherwise
+const SCHEDULE_HAVOC: usize = 256;
+
+/// Interesting 8-bit values the deterministic byte walk writes at each offset
+const INTERESTING_8: [u8; 9] = [0x80, 0xFF, 0x00, 0x01, 0x10, 0x20, 0x40, 0x64, 0x7F];
+
+/// The largest value the deterministic arithmetic walk adds to and subtracts
+/// from each byte
+const ARITH_MAX: u8 = 35;
 
-/// When mutation strategies rely on mutating a block of memory, this figure
-/// provides the ceiling for the dimensions of the block. Keep in mind that
-/// inputs may pass through multiple rounds of mutation.
-const MAX_BLOCK_CORRUPTION: usize = 512;
+/// The priority `Selection::Priority` assumes for inputs that were never
+/// given one
+const DEFAULT_PRIORITY: f64 = 1.0;
 
-/// When mutation strategies rely on mutating bits, this figure provides the
-/// ceiling for the number of bits that can be affected. Keep in mind that inputs
-/// may pass through multiple rounds of mutation.
-const MAX_BIT_CORRUPTION: usize = 64;
+/// How many extra rounds per required effective round `mutate_input` will try
+/// before giving up on `require_effective_rounds`
+const EFFECTIVE_ROUND_RETRIES: usize = 8;
+
+/// The most inputs `neighborhood` will return, the number of neighbors grows
+/// exponentially with the radius so it has to be capped somewhere
+const MAX_NEIGHBORS: usize = 4096;
+
+/// The largest share of the input DeleteRange removes unless
+/// `MutatorConfig::delete_range_fraction` says otherwise
+const DELETE_RANGE_FRACTION: f32 = 0.5;
 
 /// Hacky list of magic numbers to try and mutate and insert into random positions
 /// in the input buffer
@@ -84,8 +120,9 @@ const MAGIC_NUMBERS: &[u64] = &[
     16384,
 ];
 
-/// A list of all the different mutation strategies
-const MUTATIONS: [MutationTypes; 12] = [
+/// A list of all the different mutation strategies, in declaration order so
+/// that a strategy's index here is its `as_u8` value
+const MUTATIONS: [MutationTypes; 30] = [
     MutationTypes::ByteInsert,
     MutationTypes::ByteOverwrite,
     MutationTypes::ByteDelete,
@@ -98,8 +135,53 @@ const MUTATIONS: [MutationTypes; 12] = [
     MutationTypes::MagicByteInsert,
     MutationTypes::MagicByteOverwrite,
     MutationTypes::Splice,
+    MutationTypes::Utf8Aware,
+    MutationTypes::Swap,
+    MutationTypes::RecordShuffle,
+    MutationTypes::RecordDuplicate,
+    MutationTypes::RecordDelete,
+    MutationTypes::Reverse,
+    MutationTypes::RotateBytes,
+    MutationTypes::DictPlace,
+    MutationTypes::Concat,
+    MutationTypes::CopyWithin,
+    MutationTypes::InsertNullRun,
+    MutationTypes::InsertHighRun,
+    MutationTypes::FieldFlip,
+    MutationTypes::Scatter,
+    MutationTypes::Transpose,
+    MutationTypes::DeleteRange,
+    MutationTypes::BigramInsert,
+    MutationTypes::CaseFlip,
+];
+
+/// The mutation strategies that are treated as longshots by default
+const DEFAULT_LONGSHOTS: [MutationTypes; 4] = [
+    MutationTypes::MagicByteInsert,
+    MutationTypes::MagicByteOverwrite,
+    MutationTypes::Splice,
+    MutationTypes::Concat,
 ];
 
+/// Exposes the built-in magic numbers the Mutator inserts and overwrites with,
+/// so a harness can pre-seed its own dictionary with the same constants
+pub fn builtin_magic_numbers() -> &'static [u64] {
+    MAGIC_NUMBERS
+}
+
+/// Xorshift gets stuck producing zeros forever if its state is ever zero, so
+/// this value is substituted for any zero state handed to the Mutator
+const ZERO_STATE_SUBSTITUTE: usize = 0x9E3779B97F4A7C15;
+
+/// Makes sure an RNG state is usable by xorshift
+fn sanitize_state(state: usize) -> usize {
+    if state == 0 {
+        ZERO_STATE_SUBSTITUTE
+    } else {
+        state
+    }
+}
+
 /// Generates a random seed for the mutator by executing rdtsc() and then
 /// hashing the result
 fn generate_seed() -> usize {
@@ -112,8 +194,16 @@ fn generate_seed() -> usize {
     hasher.finish() as usize
 }
 
+/// One step of splitmix64, used to derive a fresh seed from an old one
+fn splitmix64(state: u64) -> u64 {
+    let mut z = state.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
 /// Represents some of the mutation strategies that AFL++ seems to do in "Havoc"
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum MutationTypes {
     ByteInsert,
     ByteOverwrite,
@@ -127,628 +217,4438 @@ pub enum MutationTypes {
     MagicByteInsert,
     MagicByteOverwrite,
     Splice,
+    Utf8Aware,
+    Swap,
+    RecordShuffle,
+    RecordDuplicate,
+    RecordDelete,
+    Reverse,
+    RotateBytes,
+    DictPlace,
+    Concat,
+    CopyWithin,
+    InsertNullRun,
+    InsertHighRun,
+    FieldFlip,
+    Scatter,
+    Transpose,
+    DeleteRange,
+    BigramInsert,
+    CaseFlip,
 }
 
-/// A structure that holds all the state for the Mutator
-#[derive(Clone, Default)]
-pub struct Mutator {
-    pub rng: usize,                        // The RNG we use for random
-    pub input: Vec<u8>,                    // Our current input buffer
-    pub max_size: usize,                   // Largest size an input can be
-    pub last_mutation: Vec<MutationTypes>, // The last mutation round summary
-}
+impl MutationTypes {
+    /// Determines whether or not a mutation strategy always leaves the length
+    /// of the input unchanged
+    pub fn is_length_preserving(&self) -> bool {
+        matches!(
+            self,
+            MutationTypes::ByteOverwrite
+                | MutationTypes::BlockOverwrite
+                | MutationTypes::BitFlip
+                | MutationTypes::MagicByteOverwrite
+                | MutationTypes::Swap
+                | MutationTypes::RecordShuffle
+                | MutationTypes::Reverse
+                | MutationTypes::RotateBytes
+                | MutationTypes::CopyWithin
+                | MutationTypes::FieldFlip
+                | MutationTypes::Scatter
+                | MutationTypes::Transpose
+                | MutationTypes::CaseFlip
+        )
+    }
 
-impl Mutator {
-    /// Generates a new Mutator instance with a random seed if one is not
-    /// provided
-    pub fn new(seed: Option<usize>, max_size: usize) -> Self {
-        // If pRNG seed not provided, make our own
-        let rng = if let Some(seed_val) = seed {
-            seed_val
-        } else {
-            generate_seed()
-        };
+    /// Determines whether or not a mutation strategy only ever adds bytes to
+    /// the input, these have nothing to do once the input is at max_size
+    pub fn is_growth(&self) -> bool {
+        matches!(
+            self,
+            MutationTypes::ByteInsert
+                | MutationTypes::BlockInsert
+                | MutationTypes::Grow
+                | MutationTypes::MagicByteInsert
+                | MutationTypes::RecordDuplicate
+                | MutationTypes::Concat
+                | MutationTypes::InsertNullRun
+                | MutationTypes::InsertHighRun
+                | MutationTypes::BigramInsert
+        )
+    }
 
-        Mutator {
-            rng,
-            input: Vec::with_capacity(max_size),
-            max_size,
-            last_mutation: Vec::with_capacity(MAX_STACK),
-        }
+    /// Every mutation strategy, in declaration order
+    pub fn all() -> &'static [MutationTypes] {
+        &MUTATIONS
     }
 
-    /// Picks a new random seed to use for the RNG
-    pub fn reseed(&mut self) -> usize {
-        self.rng = generate_seed();
-        self.rng
+    /// The number of mutation strategies
+    pub fn count() -> usize {
+        MUTATIONS.len()
     }
 
-    /// Xorshift pseudo-random function based on Brandon Falk's streams
-    #[inline]
-    fn rand(&mut self) -> usize {
-        // Save off current value
-        let curr = self.rng;
+    /// The strategy's small integer id, `TryFrom<u8>` maps it back
+    pub fn as_u8(&self) -> u8 {
+        *self as u8
+    }
+}
 
-        // Mutate current state with xorshift for next call
-        self.rng ^= self.rng << 13;
-        self.rng ^= self.rng >> 17;
-        self.rng ^= self.rng << 43;
+/// Implement TryFrom for MutationTypes so external schedulers can store
+/// strategies as small integers and convert them back
+impl TryFrom<u8> for MutationTypes {
+    // Dummy error
+    type Error = ();
 
-        // Return saved off value
-        curr
+    // Return value or error
+    fn try_from(val: u8) -> Result<Self, Self::Error> {
+        MUTATIONS.get(val as usize).copied().ok_or(())
     }
+}
 
-    /// Insert bytes into the input randomly
-    fn byte_insert(&mut self) {
-        // Defaults to global max, but can be hand tuned
-        const MAX_INSERTS: usize = MAX_BYTE_CORRUPTION;
+/// The byte order that magic values are emitted in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
 
-        // Determine the slack space we have
-        let slack = self.max_size - self.input.len();
+/// How the Mutator picks which corpus input to mutate
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Selection {
+    /// Every input is equally likely
+    #[default]
+    Uniform,
 
-        // If we don't have any slack, return
-        if slack == 0 {
-            return;
-        }
+    /// Inputs are picked inversely proportional to their length
+    SmallestBiased,
 
-        // Determine the ceiling
-        let ceiling = std::cmp::min(slack, MAX_INSERTS);
+    /// Inputs are picked proportional to their cached coverage
+    CoverageWeighted,
 
-        // Pick number of bytes to insert, at least 1
-        let insert_num = (self.rand() % ceiling) + 1;
+    /// Inputs are picked proportional to their priority in the corpus, or the
+    /// highest priority input is always picked if `greedy` is set. Inputs
+    /// without a priority count as `DEFAULT_PRIORITY`
+    Priority { greedy: bool },
+}
+
+/// A distribution of input sizes that Grow and Truncate steer inputs towards
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SizeDist {
+    /// Every size from `min` to `max` inclusive is equally likely
+    Uniform { min: usize, max: usize },
+}
 
-        // Iterate through and apply insertions, duplicate idxs is ok
-        for _ in 0..insert_num {
-            // Pick an index
-            let curr_idx = self.rand() % self.input.len();
+/// Tunable knobs for the Mutator, the defaults preserve the stock behavior
+#[derive(Clone, Default)]
+pub struct MutatorConfig {
+    pub utf8_aware: bool,             // Codepoint-level mutations for text targets
+    pub stable_splice_donors: bool,   // Select splice donors by content, not index
+    pub preserve_prefix: usize,       // Leading bytes that mutations never modify
+    pub preserve_suffix: usize,       // Trailing bytes that mutations never modify
+    pub record_delimiter: Option<u8>, // Splits inputs into records for record ops
+    pub magic_width: Option<usize>,   // Emit magic values at exactly this width
+    pub focus: Option<Range<usize>>,  // Byte range that mutations are confined to
+    pub large_splice_bias: bool,      // Bias splice grafts towards larger blocks
+    pub splice_keep_recipient_head: bool, // Splice keeps the recipient's head
+    pub dictionary_scratch: bool,     // Empty corpus builds inputs from the dictionary
+    pub distinct_bit_flips: bool,     // BitFlip never flips the same bit twice
+    pub record_boundaries: Vec<usize>, // Field offsets that Truncate snaps down to
+    pub accumulate_history: bool,     // Keep a rolling log of mutation sequences
+    pub magic_endianness: Option<Endianness>, // Magic byte order, None is random
+    pub selection: Selection,         // How corpus inputs are picked for mutation
+    pub target_size: Option<SizeDist>, // Sizes that Grow/Truncate steer towards
+    pub magic_width_weights: Option<[usize; 4]>, // u8/u16/u32/u64 magic weights
+    pub schedule_havoc: Option<usize>, // Havoc outputs per `schedule` call
+    pub tag_aware_splice: bool,       // Prefer splice donors with the same tag
+    pub scatter_prob: f32,            // Per-byte Scatter chance, 0.0 disables it
+    pub require_effective_rounds: usize, // Rounds that must change the input
+    pub gen_scratch_rate: Option<usize>, // Scratch input percentage, 0 disables
+    pub profile: bool,                // Time every strategy into MutatorStats
+    pub splice_exclude_self: bool,    // Splice donors avoid the recipient input
+    pub check_invariants: bool,       // Run the invariant check in release builds
+    pub delete_range_fraction: Option<f32>, // Largest share DeleteRange removes
+    pub case_flip: bool,              // Letter case mutations for text targets
+    pub walk_mutate: bool,            // corpus_walk applies one mutation per input
+    pub grow_at_capacity: bool,       // Growth strategies stay selectable at max_size
+    pub min_size: usize,              // Finished inputs are padded up to this size
+}
 
-            // Pick a byte to insert
-            let byte = (self.rand() % 256) as u8;
+/// Counters the Mutator keeps about itself, per-strategy timings are only
+/// collected while `MutatorConfig::profile` is set
+#[derive(Clone, Debug, Default)]
+pub struct MutatorStats {
+    nanos: Vec<u64>, // Wall time spent in each strategy, indexed by `as_u8`
+    runs: Vec<u64>,  // Number of timed runs of each strategy
+}
 
-            // Insert it
-            self.input.insert(curr_idx, byte);
+impl MutatorStats {
+    /// Adds one timed run of a strategy
+    fn record_timing(&mut self, ty: MutationTypes, elapsed: Duration) {
+        if self.nanos.is_empty() {
+            self.nanos = vec![0; MutationTypes::count()];
+            self.runs = vec![0; MutationTypes::count()];
         }
+
+        self.nanos[ty.as_u8() as usize] += elapsed.as_nanos() as u64;
+        self.runs[ty.as_u8() as usize] += 1;
     }
 
-    /// Overwrite bytes in the input randomly
-    fn byte_overwrite(&mut self) {
-        // Defaults to global max, but can be hand tuned
-        const MAX_OVERWRITES: usize = MAX_BYTE_CORRUPTION;
+    /// Retrieves the total wall time and number of timed runs of every
+    /// strategy that ran while profiling
+    #[allow(dead_code)]
+    pub fn timings(&self) -> Vec<(MutationTypes, Duration, u64)> {
+        MutationTypes::all()
+            .iter()
+            .filter_map(|&ty| {
+                let idx = ty.as_u8() as usize;
+                let runs = *self.runs.get(idx)?;
+                (runs > 0).then(|| (ty, Duration::from_nanos(self.nanos[idx]), runs))
+            })
+            .collect()
+    }
+}
 
-        // Determine how many bytes we can overwrite
-        let ceiling = std::cmp::min(self.input.len(), MAX_OVERWRITES);
+/// One applied mutation round, the RNG state right before the strategy ran is
+/// enough to replay it exactly on the same input with the same configuration
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MutationRecord {
+    pub mutation: MutationTypes, // The strategy that was applied
+    pub rng: usize,              // RNG state the strategy started from
+}
 
-        // Pick a number of bytes to overwrite
-        let overwrite_num = (self.rand() % ceiling) + 1;
+/// One change between a base input and a mutated input, ranges are offsets
+/// into the base
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EditOp {
+    Insert(usize, Vec<u8>),         // Bytes inserted before a base offset
+    Delete(Range<usize>),           // Base bytes that were removed
+    Replace(Range<usize>, Vec<u8>), // Base bytes that were replaced
+}
 
-        // Iterate through and apply overwrites
-        for _ in 0..overwrite_num {
-            // Pick an index
-            let curr_idx = self.rand() % self.input.len();
+/// Current version of the `save_record` file format
+const RECORD_VERSION: u32 = 1;
 
-            // Pick a byte to overwrite with
-            let byte = (self.rand() % 256) as u8;
+/// Magic bytes that start a `save_record` file
+const RECORD_MAGIC: &[u8; 4] = b"LREC";
 
-            // Overwrite it
-            self.input[curr_idx] = byte;
-        }
-    }
+/// A dictionary entry, tokens that are known to live at a fixed position (ie
+/// a file signature at offset 0) can carry that position as a hint
+#[derive(Clone, Debug, Default)]
+pub struct DictToken {
+    pub bytes: Vec<u8>,                  // The token itself
+    pub preferred_offset: Option<usize>, // Where the token usually appears
+}
 
-    /// Delete bytes in the input randomly
-    fn byte_delete(&mut self) {
-        // Defaults to global max, but can be hand tuned
-        const MAX_DELETES: usize = MAX_BYTE_CORRUPTION;
+/// How a declared field is interpreted, which decides what boundary values
+/// are interesting for it
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldKind {
+    Unsigned, // Plain unsigned integer
+    Signed,   // Two's complement signed integer
+    Length,   // Unsigned integer that counts bytes
+}
 
-        // Determine how many bytes we can delete
-        let ceiling = std::cmp::min(self.input.len() - 1, MAX_DELETES);
+/// A little-endian integer field at a fixed offset in the input
+#[derive(Clone, Copy, Debug)]
+struct Field {
+    offset: usize,   // Where the field starts
+    width: usize,    // Size of the field in bytes, 1 through 8
+    kind: FieldKind, // How the field is interpreted
+}
 
-        // If the ceiling is 0, return
-        if ceiling == 0 {
-            return;
-        }
+/// The Mutator's own input buffer is a Vec, so it can be handed straight to
+/// the length-changing core strategies
+impl ByteBuffer for Vec<u8> {
+    fn as_slice(&self) -> &[u8] {
+        self
+    }
 
-        // Pick a number of bytes to delete
-        let delete_num = (self.rand() % ceiling) + 1;
+    fn insert(&mut self, idx: usize, byte: u8) {
+        Vec::insert(self, idx, byte);
+    }
 
-        // Iterate through and apply the deletes
-        for _ in 0..delete_num {
-            // Pick an index
-            let curr_idx = self.rand() % self.input.len();
+    fn remove(&mut self, idx: usize) {
+        Vec::remove(self, idx);
+    }
 
-            // Remove it
-            self.input.remove(curr_idx);
-        }
+    fn remove_range(&mut self, range: Range<usize>) {
+        self.drain(range);
     }
 
-    /// Grabs a block from the input, and insert it randomly somewhere else
-    fn block_insert(&mut self) {
-        // Defaults to global max, but can be hand tuned
-        const MAX_BLOCK_SIZE: usize = MAX_BLOCK_CORRUPTION;
-        let mut block = [0u8; MAX_BLOCK_SIZE];
+    fn truncate(&mut self, len: usize) {
+        Vec::truncate(self, len);
+    }
+}
 
-        // Determine the slack space in the input we have since we're growing
-        let slack = self.max_size - self.input.len();
+/// Callback invoked after each applied mutation with the strategy that was
+/// applied and the state of the input buffer afterwards
+pub type ObserverFn = dyn FnMut(&MutationTypes, &[u8]) + Send;
 
-        // If we don't have any slack, return
-        if slack == 0 {
-            return;
-        }
+/// The observer is shared so that the Mutator can still be cloned, and behind
+/// a Mutex so that the Mutator can still be sent to another thread
+type MutationObserver = Arc<Mutex<Box<ObserverFn>>>;
 
-        // Determine a ceiling
-        let mut ceiling = std::cmp::min(slack, MAX_BLOCK_SIZE);
+/// Callback invoked on every finished input to fix it up, ie recomputing a
+/// length field or a checksum that the mutations broke
+pub type PostProcessFn = dyn FnMut(&mut Vec<u8>) + Send;
 
-        // If the ceiling is larger than the input, adjust it
-        if ceiling > self.input.len() {
-            ceiling = self.input.len();
-        }
+/// Shared for the same reason as the observer
+type PostProcessor = Arc<Mutex<Box<PostProcessFn>>>;
 
-        // Determine a block size
-        let block_size = (self.rand() % ceiling) + 1;
+/// Predicate every finished input has to satisfy when fuzzing the Mutator
+/// itself, returns false if the input violates it
+pub type InvariantFn = dyn Fn(&[u8]) -> bool + Send + Sync;
 
-        // Determine the end range we can start from for the block
-        let max_start = self.input.len() - block_size;
+/// Per-input weights for a weighted selection mode, they're kept until the
+/// corpus they were computed from changes
+#[derive(Clone, Default)]
+struct SelectionCache {
+    key: Option<(usize, u64, Selection)>, // Corpus address, version, and mode
+    weights: Vec<f64>,                    // Weight of each input
+    total: f64,                           // Sum of the weights
+}
 
-        // Determine where to start reading the block
-        let block_start = self.rand() % (max_start + 1);
+/// A structure that holds all the state for the Mutator
+#[derive(Clone, Default)]
+pub struct Mutator {
+    pub rng: usize,                        // The RNG we use for random
+    pub input: Vec<u8>,                    // Our current input buffer
+    pub max_size: usize,                   // Largest size an input can be
+    pub last_mutation: Vec<MutationTypes>, // The last mutation round summary
+    longshots: Vec<MutationTypes>,         // Strategies applied at longshot rate
+    pub config: MutatorConfig,             // Optional mutation behavior knobs
+    observer: Option<MutationObserver>,    // Per-mutation callback
+    dictionary: Vec<DictToken>,            // Tokens for dictionary mutations
+    disabled: u64,                         // Bitmask of disabled strategies
+    weights: Vec<usize>,                   // Strategy weights, empty is uniform
+    magic: Vec<u64>,                       // Magic numbers, empty is the builtin
+    history: VecDeque<Vec<MutationTypes>>, // Rolling log of last_mutation
+    last_parent: Option<usize>,            // Corpus index the input came from
+    temperature: Option<f32>,              // Mutation aggression, None is 1.0
+    fields: Vec<Field>,                    // Declared fields for FieldFlip
+    alphabet: Vec<u8>,                     // Bytes random data uses, empty is all
+    records: Vec<MutationRecord>,          // Replayable rounds for current input
+    warned_over_cap: bool,                 // Warned about a base over max_size
+    stats: MutatorStats,                   // Self-profiling counters
+    bigrams: Vec<([u8; 2], usize)>,        // Learned byte pairs and their counts
+    walk_cursor: usize,                    // Next corpus index for corpus_walk
+    post_process: Option<PostProcessor>,   // Fixes up every finished input
+    invariant: Option<Arc<InvariantFn>>,   // Checked on every finished input
+    window_start: usize,                   // Offset of the window being mutated
+    window_tail: usize,                    // Bytes set aside after the window
+    selection: SelectionCache,             // Cached corpus selection weights
+}
 
-        // Copy the block into the block array
-        block[..block_size].copy_from_slice(&self.input[block_start..block_start + block_size]);
+/// Chainable configuration for a Mutator that is validated all at once when
+/// the Mutator is built, see `Mutator::builder`
+#[derive(Clone, Default)]
+pub struct MutatorBuilder {
+    seed: Option<usize>,                   // pRNG seed, None is random
+    max_size: usize,                       // Largest size an input can be
+    config: MutatorConfig,                 // Optional mutation behavior knobs
+    dictionary: Vec<DictToken>,            // Tokens for dictionary mutations
+    taboo: Vec<u8>,                        // Bytes random data never emits
+    longshots: Option<Vec<MutationTypes>>, // Longshot set, None is the default
+    disabled: u64,                         // Bitmask of disabled strategies
+    temperature: Option<f32>,              // Mutation aggression, None is 1.0
+    focus: Option<Range<usize>>,           // Applied over `config`'s focus
+    min_size: Option<usize>,               // Applied over `config`'s min_size
+    weights: Vec<(MutationTypes, usize)>,  // Strategy selection weights
+    magic: Option<Vec<u64>>,               // Magic numbers, None is the builtin
+}
 
-        // Determine where to insert the block
-        let block_insert = self.rand() % self.input.len();
+impl MutatorBuilder {
+    /// Seeds the pRNG, a random seed is used if this isn't called
+    pub fn seed(mut self, seed: usize) -> Self {
+        self.seed = Some(seed);
+        self
+    }
 
-        // Use insert calls (slow, but readable and who cares?)
-        for (i, &byte) in block[..block_size].iter().enumerate() {
-            self.input.insert(block_insert + i, byte);
-        }
+    /// Sets the largest size an input can be, this is required
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
     }
 
-    /// Grabs a block from the input and copy it over to another location
-    fn block_overwrite(&mut self) {
-        // Defaults to global max, but can be hand tuned
-        const MAX_BLOCK_SIZE: usize = MAX_BLOCK_CORRUPTION;
-        let mut block = [0u8; MAX_BLOCK_SIZE];
+    /// Replaces the whole set of tunable knobs, the more specific builder
+    /// methods called after this one still apply on top
+    #[allow(dead_code)]
+    pub fn config(mut self, config: MutatorConfig) -> Self {
+        self.config = config;
+        self
+    }
 
-        // Determine a ceiling of block size
-        let ceiling = std::cmp::min(self.input.len(), MAX_BLOCK_SIZE);
+    /// Sets the dictionary, see `Mutator::load_dictionary_positional`
+    #[allow(dead_code)]
+    pub fn dictionary(mut self, tokens: Vec<DictToken>) -> Self {
+        self.dictionary = tokens;
+        self
+    }
 
-        // Pick a block size
-        let block_size = (self.rand() % ceiling) + 1;
+    /// Sets the bytes random data never emits, see `Mutator::set_taboo_bytes`
+    #[allow(dead_code)]
+    pub fn taboo_bytes(mut self, bytes: &[u8]) -> Self {
+        self.taboo = bytes.to_vec();
+        self
+    }
 
-        // Determine the end range we can start from for the block reading, but
-        // also this is the block writing start as well
-        let max_start = self.input.len() - block_size;
+    /// Confines mutations to a byte range, see `Mutator::set_focus`
+    #[allow(dead_code)]
+    pub fn focus(mut self, range: Range<usize>) -> Self {
+        self.focus = Some(range);
+        self
+    }
 
-        // Determine where to start reading the block
-        let block_start = self.rand() % (max_start + 1);
+    /// Sets the smallest size a finished input can be, see
+    /// `MutatorConfig::min_size`
+    #[allow(dead_code)]
+    pub fn min_size(mut self, min_size: usize) -> Self {
+        self.min_size = Some(min_size);
+        self
+    }
 
-        // Copy the block into the block array
-        block[..block_size].copy_from_slice(&self.input[block_start..block_start + block_size]);
+    /// Sets strategy selection weights, see `Mutator::set_weights`
+    #[allow(dead_code)]
+    pub fn weights(mut self, weights: &[(MutationTypes, usize)]) -> Self {
+        self.weights = weights.to_vec();
+        self
+    }
 
-        // Determine where to start overwriting
-        let overwrite_start = self.rand() % (max_start + 1);
+    /// Replaces the builtin magic numbers, see `Mutator::set_magic_numbers`
+    #[allow(dead_code)]
+    pub fn magic_numbers(mut self, magic: &[u64]) -> Self {
+        self.magic = Some(magic.to_vec());
+        self
+    }
 
-        // Overwrite those bytes
-        self.input[overwrite_start..overwrite_start + block_size]
-            .copy_from_slice(&block[..block_size]);
+    /// Overrides the longshot set, see `Mutator::set_longshot`
+    #[allow(dead_code)]
+    pub fn longshots(mut self, types: &[MutationTypes]) -> Self {
+        self.longshots = Some(types.to_vec());
+        self
     }
 
-    /// Removes a random block from the input buffer
-    fn block_delete(&mut self) {
-        // Defaults to global max, but can be hand tuned
-        const MAX_BLOCK_SIZE: usize = MAX_BLOCK_CORRUPTION;
+    /// Sets the mutation temperature, see `Mutator::set_temperature`
+    #[allow(dead_code)]
+    pub fn temperature(mut self, t: f32) -> Self {
+        self.temperature = Some(t);
+        self
+    }
 
-        // Determine how much we can delete
-        let ceiling = std::cmp::min(self.input.len() - 1, MAX_BLOCK_SIZE);
+    /// Allows a mutation strategy that an earlier `disable` turned off
+    #[allow(dead_code)]
+    pub fn enable(mut self, ty: MutationTypes) -> Self {
+        self.disabled &= !(1 << ty as u64);
+        self
+    }
 
-        // If we have a ceiling of 0, just return
-        if ceiling == 0 {
-            return;
-        }
+    /// Stops a mutation strategy from ever being selected
+    #[allow(dead_code)]
+    pub fn disable(mut self, ty: MutationTypes) -> Self {
+        self.disabled |= 1 << ty as u64;
+        self
+    }
 
-        // Pick a block size for deletion
-        let block_size = (self.rand() % ceiling) + 1;
+    /// Validates the configuration and builds the Mutator. Unlike the setters
+    /// on the Mutator, nonsense is rejected rather than clamped or ignored
+    pub fn build(mut self) -> Result<Mutator, LucidErr> {
+        if self.max_size == 0 {
+            return Err(LucidErr::Mutator("max_size must be above 0".to_string()));
+        }
 
-        // Determine the end range to start deleting from
-        let max_start = self.input.len() - block_size;
+        // The specific knobs win over the whole config regardless of order
+        if let Some(focus) = self.focus.take() {
+            self.config.focus = Some(focus);
+        }
 
-        // Pick a place to start deleting from
-        let block_start = self.rand() % (max_start + 1);
+        if let Some(min_size) = self.min_size {
+            self.config.min_size = min_size;
+        }
 
-        // Delete that block
-        self.input.drain(block_start..block_start + block_size);
-    }
+        if self.config.min_size > self.max_size {
+            return Err(LucidErr::Mutator(format!(
+                "min_size {} is above max_size {}",
+                self.config.min_size, self.max_size
+            )));
+        }
 
-    /// Generates a random input from scratch, not likely to be a great strategy
-    fn generate_random_input(&mut self) {
-        // Pick a size for the input
-        let input_size = (self.rand() % self.max_size) + 1;
+        if let Some(focus) = &self.config.focus {
+            if focus.is_empty() || focus.end > self.max_size {
+                return Err(LucidErr::Mutator(format!(
+                    "Focus {:?} is empty or past max_size {}",
+                    focus, self.max_size
+                )));
+            }
+        }
 
-        // Re-size the input vector
-        self.input.resize(input_size, 0);
+        if let Some(t) = self.temperature {
+            if !(0.0..=1.0).contains(&t) {
+                return Err(LucidErr::Mutator(format!(
+                    "Temperature {} is outside of 0.0 to 1.0",
+                    t
+                )));
+            }
+        }
 
-        // Fill in the data randomly
-        for i in 0..input_size {
-            self.input[i] = (self.rand() % 256) as u8;
+        let taboo: HashSet<u8> = self.taboo.iter().copied().collect();
+        if taboo.len() == 256 {
+            return Err(LucidErr::Mutator("Every byte value is taboo".to_string()));
         }
-    }
 
-    /// Randomly flips bits in the input buffer
-    fn bit_flip(&mut self) {
-        // Determine the number of bits in the input
-        let num_bits = self.input.len() * 8;
+        if self.magic.as_ref().is_some_and(|magic| magic.is_empty()) {
+            return Err(LucidErr::Mutator("Magic numbers are empty".to_string()));
+        }
 
-        // Determine the ceiling of what we can flip
-        let ceiling = std::cmp::min(num_bits, MAX_BIT_CORRUPTION);
+        let weight = |ty: MutationTypes| {
+            self.weights
+                .iter()
+                .rev()
+                .find(|(weighted, _)| *weighted == ty)
+                .map_or(1, |&(_, weight)| weight)
+        };
 
-        // Determine the number of bits to flip (at least 1)
-        let num_flips = (self.rand() % ceiling) + 1;
+        if MUTATIONS
+            .iter()
+            .all(|&ty| self.disabled & (1 << ty as u64) != 0 || weight(ty) == 0)
+        {
+            return Err(LucidErr::Mutator(
+                "Every mutation strategy is disabled".to_string(),
+            ));
+        }
 
-        // Go through and flip bits
-        for _ in 0..num_flips {
-            // Choose a random bit to flip
-            let bit_position = self.rand() % num_bits;
+        let mut mutator = Mutator::new(self.seed, self.max_size);
+        mutator.config = self.config;
+        mutator.disabled = self.disabled;
+        mutator.temperature = self.temperature;
+        mutator.load_dictionary_positional(self.dictionary);
+        mutator.set_taboo_bytes(&self.taboo);
+        mutator.set_weights(&self.weights);
+        if let Some(longshots) = self.longshots {
+            mutator.set_longshot(&longshots);
+        }
+        if let Some(magic) = self.magic {
+            mutator.set_magic_numbers(&magic);
+        }
 
-            // Calculate which byte this bit is in
-            let byte_index = bit_position / 8;
+        Ok(mutator)
+    }
+}
 
-            // Calculate which bit within the byte to flip
-            let bit_index = bit_position % 8;
+impl Mutator {
+    /// Generates a new Mutator instance with a random seed if one is not
+    /// provided
+    pub fn new(seed: Option<usize>, max_size: usize) -> Self {
+        // If pRNG seed not provided, make our own
+        let rng = if let Some(seed_val) = seed {
+            seed_val
+        } else {
+            generate_seed()
+        };
 
-            // Flip the bit
-            self.input[byte_index] ^= 1 << bit_index;
+        Mutator {
+            rng: sanitize_state(rng),
+            input: Vec::with_capacity(max_size),
+            max_size,
+            last_mutation: Vec::with_capacity(MAX_STACK),
+            longshots: DEFAULT_LONGSHOTS.to_vec(),
+            config: MutatorConfig::default(),
+            observer: None,
+            dictionary: Vec::new(),
+            disabled: 0,
+            weights: Vec::new(),
+            magic: Vec::new(),
+            history: VecDeque::new(),
+            last_parent: None,
+            temperature: None,
+            fields: Vec::new(),
+            alphabet: Vec::new(),
+            records: Vec::new(),
+            warned_over_cap: false,
+            stats: MutatorStats::default(),
+            bigrams: Vec::new(),
+            walk_cursor: 0,
+            post_process: None,
+            invariant: None,
+            window_start: 0,
+            window_tail: 0,
+            selection: SelectionCache::default(),
         }
     }
 
-    /// Inserts a random byte block into the input buffer
-    fn grow(&mut self) {
-        // Determine maximum size to grow
-        let slack = self.max_size - self.input.len();
-        if slack == 0 {
-            return;
+    /// Starts building a Mutator with chainable configuration, `max_size` has
+    /// to be set before `build`
+    pub fn builder() -> MutatorBuilder {
+        MutatorBuilder::default()
+    }
+
+    /// Deterministically generates a batch of inputs for bootstrapping a
+    /// corpus, each seed gets its own default Mutator which produces `per_seed`
+    /// mutated inputs. The same seeds always yield the same batch
+    #[allow(dead_code)]
+    pub fn ensemble(
+        seeds: &[usize],
+        max_size: usize,
+        corpus: &dyn CorpusView,
+        per_seed: usize,
+    ) -> Vec<Vec<u8>> {
+        let mut batch = Vec::with_capacity(seeds.len() * per_seed);
+
+        for &seed in seeds {
+            let mut mutator = Mutator::new(Some(seed), max_size);
+            for _ in 0..per_seed {
+                mutator.mutate_input(corpus);
+                batch.push(mutator.input.clone());
+            }
         }
 
-        // Pick size of block
-        let size = (self.rand() % slack) + 1;
+        batch
+    }
 
-        // Pick an index to add to
-        let idx = self.rand() % self.input.len();
+    /// Determines whether or not a mutation strategy is currently a member of
+    /// the longshot set
+    pub fn is_longshot(&self, mutation: MutationTypes) -> bool {
+        self.longshots.contains(&mutation)
+    }
 
-        // Pick byte to place in there
-        let byte = (self.rand() % 256) as u8;
+    /// Overrides which mutation strategies are considered longshots, any
+    /// strategy not in `types` will be placed in the common pool
+    pub fn set_longshot(&mut self, types: &[MutationTypes]) {
+        self.longshots.clear();
+        self.longshots.extend_from_slice(types);
+    }
 
-        // Insert there
-        for _ in 0..size {
-            self.input.insert(idx, byte);
-        }
+    /// Allows a mutation strategy to be selected again after it was disabled,
+    /// every strategy is enabled by default
+    #[allow(dead_code)]
+    pub fn enable(&mut self, ty: MutationTypes) {
+        self.disabled &= !(1 << ty as u64);
     }
 
-    /// Truncates the input a random amount of bytes but always leaves at least
-    /// one byte
-    fn truncate(&mut self) {
-        // Determine how much we can shrink
-        let slack = self.input.len() - 1;
-        if slack == 0 {
+    /// Stops a mutation strategy from ever being selected, ie `Splice` when
+    /// there's no corpus to speak of
+    #[allow(dead_code)]
+    pub fn disable(&mut self, ty: MutationTypes) {
+        self.disabled |= 1 << ty as u64;
+    }
+
+    /// Sets how often each strategy is selected relative to the others, every
+    /// strategy has a weight of 1 unless given here and a weight of 0 stops a
+    /// strategy from being selected just like `disable`
+    pub fn set_weights(&mut self, weights: &[(MutationTypes, usize)]) {
+        self.weights.clear();
+        if weights.is_empty() {
             return;
         }
 
-        // Pick an index to truncate at, can't be zero
-        let idx = (self.rand() % slack) + 1;
-
-        // Truncate
-        self.input.truncate(idx);
+        self.weights.resize(MUTATIONS.len(), 1);
+        for &(ty, weight) in weights {
+            self.weights[ty.as_u8() as usize] = weight;
+        }
     }
 
-    /// Takes a magic number value and mutates it
-    fn mutate_magic(&mut self, magic: u64) -> Vec<u8> {
-        // Mutate the magic value
-        let magic = match self.rand() % 14 {
-            0 => magic,
-            1 => magic & 0xFF,
-            2 => magic & 0xFFFF,
-            3 => magic & 0xFFFFFFFF,
-            4 => magic - 1,
-            5 => magic + 1,
-            6 => !magic,                 // Bitwise NOT
-            7 => magic << 1,             // Left shift by 1
-            8 => magic >> 1,             // Right shift by 1
-            9 => magic.rotate_left(8),   // Rotate left by 8 bits
-            10 => magic.rotate_right(8), // Rotate right by 8 bits
-            11 => magic ^ 0xFFFFFFFF,    // XOR with all 1s (32-bit)
-            12 => magic.swap_bytes(),    // Swap byte order
-            13 => {
-                // Flip a random bit
-                let bit = self.rand() % 64;
-                magic ^ (1 << bit)
-            }
-            _ => unreachable!(),
-        };
+    /// Gets the selection weight of a strategy, see `set_weights`
+    fn weight(&self, mutation: MutationTypes) -> usize {
+        self.weights
+            .get(mutation.as_u8() as usize)
+            .copied()
+            .unwrap_or(1)
+    }
 
-        // Convert to bytes
-        let magic_bytes = magic.to_ne_bytes();
+    /// Replaces the builtin magic numbers that the magic strategies insert and
+    /// overwrite with, an empty slice goes back to the builtin ones
+    pub fn set_magic_numbers(&mut self, magic: &[u64]) {
+        self.magic = magic.to_vec();
+    }
 
-        // Randomly truncate bytes
-        match self.rand() % 15 {
-            0 => magic_bytes.to_vec(),       // All 8 bytes (u64)
-            1 => magic_bytes[0..4].to_vec(), // First 4 bytes (u32)
-            2 => magic_bytes[4..8].to_vec(), // Last 4 bytes (u32)
-            3 => magic_bytes[0..2].to_vec(), // First 2 bytes (u16)
-            4 => magic_bytes[2..4].to_vec(), // Second 2 bytes (u16)
-            5 => magic_bytes[4..6].to_vec(), // Third 2 bytes (u16)
-            6 => magic_bytes[6..8].to_vec(), // Last 2 bytes (u16)
-            7 => vec![magic_bytes[0]],       // 1st byte (u8)
-            8 => vec![magic_bytes[1]],       // 2nd byte (u8)
-            9 => vec![magic_bytes[2]],       // 3rd byte (u8)
-            10 => vec![magic_bytes[3]],      // 4th byte (u8)
-            11 => vec![magic_bytes[4]],      // 5th byte (u8)
-            12 => vec![magic_bytes[5]],      // 6th byte (u8)
-            13 => vec![magic_bytes[6]],      // 7th byte (u8)
-            14 => vec![magic_bytes[7]],      // 8th byte (u8)
-            _ => unreachable!(),
+    /// The magic numbers the magic strategies currently use
+    pub fn magic_numbers(&self) -> &[u64] {
+        if self.magic.is_empty() {
+            builtin_magic_numbers()
+        } else {
+            &self.magic
         }
     }
 
-    /// Inserts magic bytes into the input buffer after optionally mutating
-    /// the bytes
-    fn magic_byte_insert(&mut self) {
-        // Defaults to global max, but can be hand tuned
-        const MAX_INSERTS: usize = MAX_BYTE_CORRUPTION;
+    /// Sets how aggressive mutation is, from 0.0 to 1.0. This scales both the
+    /// upper bound on stacked mutation rounds and the longshot rate, so a
+    /// harness can start a campaign hot and decay the temperature as the
+    /// corpus matures. At 0.0 only a single round is applied and longshots are
+    /// never picked, 1.0 is the default behavior
+    #[allow(dead_code)]
+    pub fn set_temperature(&mut self, t: f32) {
+        self.temperature = Some(t.clamp(0.0, 1.0));
+    }
 
-        // Determine the slack space we have
-        let slack = self.max_size - self.input.len();
+    /// Retrieves the current mutation temperature
+    pub fn temperature(&self) -> f32 {
+        self.temperature.unwrap_or(1.0)
+    }
 
-        // If we don't have any slack space, return
-        if slack == 0 {
-            return;
-        }
+    /// Scales an upper bound on mutation rounds by the temperature, there is
+    /// always at least one round
+    fn scaled_rounds(&self, max_rounds: f32) -> usize {
+        std::cmp::max((max_rounds * self.temperature()).round() as usize, 1)
+    }
 
-        // Determine the ceiling
-        let ceiling = std::cmp::min(slack, MAX_INSERTS);
+    /// Changes the largest size an input can be, this can be lower than the
+    /// length of the input currently loaded so all of the slack math has to
+    /// saturate rather than assume `input.len() <= max_size`
+    pub fn set_max_size(&mut self, max_size: usize) {
+        self.max_size = max_size;
+    }
 
-        // Pick number of bytes to insert, at least 1
-        let insert_num = (self.rand() % ceiling) + 1;
+    /// Declares a delimiter byte (ie `\n` or `\x00`) that splits inputs into
+    /// records, this enables the record-aware mutation strategies
+    #[allow(dead_code)]
+    pub fn set_record_delimiter(&mut self, delim: u8) {
+        self.config.record_delimiter = Some(delim);
+    }
 
-        // Divide that by 8 to determine how many u64s will fit
-        let num_u64 = insert_num / 8;
+    /// Restricts all mutations to a byte range of the input, only strategies
+    /// that preserve the input's length are applied while a focus is set.
+    /// Passing None lets mutations touch the whole buffer again
+    #[allow(dead_code)]
+    pub fn set_focus(&mut self, range: Option<Range<usize>>) {
+        self.config.focus = range;
+    }
 
-        // Insert up to num_u64 u64 values, likely much smaller
-        for _ in 0..num_u64 {
-            // Pick an index to insert at
-            let idx = self.rand() % self.input.len();
+    /// Installs a callback that is handed each mutation strategy along with
+    /// the whole input buffer after that strategy was applied, this lets a
+    /// consumer watch an input's mutation progress round by round
+    #[allow(dead_code)]
+    pub fn set_observer(&mut self, f: Box<ObserverFn>) {
+        self.observer = Some(Arc::new(Mutex::new(f)));
+    }
 
-            // Pick a magic value
-            let magic = MAGIC_NUMBERS[self.rand() % MAGIC_NUMBERS.len()];
+    /// Installs a callback that every finished input is passed through after
+    /// all of the mutation rounds, so format fixups can be applied
+    #[allow(dead_code)]
+    pub fn set_post_processor(&mut self, f: Box<PostProcessFn>) {
+        self.post_process = Some(Arc::new(Mutex::new(f)));
+    }
 
-            // Randomly corrupt the magic number
-            let magic_bytes = if self.rand() % 2 == 0 {
-                self.mutate_magic(magic)
-            } else {
-                magic.to_ne_bytes().to_vec()
+    /// Installs an invariant that every finished input must satisfy, checked
+    /// after the post-processor runs. A violation panics, which `try_mutate`
+    /// turns into an error. The check only runs in debug builds unless
+    /// `MutatorConfig::check_invariants` is set
+    #[allow(dead_code)]
+    pub fn set_invariant_check(&mut self, f: Box<InvariantFn>) {
+        self.invariant = Some(Arc::from(f));
+    }
+
+    /// Replaces the dictionary with tokens that may carry a preferred offset,
+    /// this enables the dictionary placement mutation strategy
+    pub fn load_dictionary_positional(&mut self, tokens: Vec<DictToken>) {
+        self.dictionary = tokens.into_iter().filter(|t| !t.bytes.is_empty()).collect();
+    }
+
+    /// Counts the byte pairs across every input in the corpus and keeps the
+    /// `MAX_BIGRAMS` most frequent ones, this enables the bigram insertion
+    /// strategy. Frequent pairs tend to be separators and other structure, ie
+    /// `, ` in JSON or `&` followed by a key in a query string. Relearning
+    /// replaces the old table
+    #[allow(dead_code)]
+    pub fn learn_bigrams(&mut self, corpus: &dyn CorpusView) {
+        let mut counts: HashMap<[u8; 2], usize> = HashMap::new();
+        for idx in 0..corpus.num_inputs() {
+            let Some(input) = corpus.get_input(idx) else {
+                continue;
             };
 
-            // Insert magic bytes
-            for (i, &byte) in magic_bytes.iter().enumerate() {
-                self.input.insert(idx + i, byte);
+            for pair in input.windows(2) {
+                *counts.entry([pair[0], pair[1]]).or_insert(0) += 1;
             }
         }
+
+        // Most frequent first, ties broken by the pair so the table is stable
+        let mut bigrams: Vec<([u8; 2], usize)> = counts.into_iter().collect();
+        bigrams.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        bigrams.truncate(MAX_BIGRAMS);
+
+        self.bigrams = bigrams;
     }
 
-    /// Overwrites randomly selected input buffer data with magic bytes that are
-    /// optionally mutated
-    fn magic_byte_overwrite(&mut self) {
-        // If the input isn't at least 8 bytes, just NOP
-        if self.input.len() < 8 {
-            return;
+    /// Keeps random byte generation (byte inserts and overwrites, grows,
+    /// scatters, and scratch inputs) from ever emitting the `bytes` given, ie
+    /// delimiters that would break a target's parsing early. Draws are
+    /// remapped modulo the allowed set. An empty set allows everything again,
+    /// and a set covering every byte value is ignored since there'd be
+    /// nothing left to emit
+    pub fn set_taboo_bytes(&mut self, bytes: &[u8]) {
+        let allowed: Vec<u8> = (0..=255u8).filter(|byte| !bytes.contains(byte)).collect();
+
+        // Nothing taboo, or everything taboo, both mean all bytes are allowed
+        if allowed.len() == 256 || allowed.is_empty() {
+            self.alphabet.clear();
+        } else {
+            self.alphabet = allowed;
         }
+    }
 
-        // Defaults to global max, but can be hand tuned
-        const MAX_OVERWRITES: usize = MAX_BYTE_CORRUPTION;
+    /// Declares a little-endian integer field of `width` bytes (clamped to 1
+    /// through 8) at `offset`, this enables the FieldFlip mutation strategy
+    /// which overwrites declared fields with boundary values for their kind
+    #[allow(dead_code)]
+    pub fn declare_field(&mut self, offset: usize, width: usize, kind: FieldKind) {
+        self.fields.push(Field {
+            offset,
+            width: width.clamp(1, 8),
+            kind,
+        });
+    }
 
-        // Determine how many bytes we can overwrite
-        let ceiling = std::cmp::min(self.input.len(), MAX_OVERWRITES);
+    /// Picks a new random seed to use for the RNG
+    pub fn reseed(&mut self) -> usize {
+        self.set_rng_state(generate_seed());
+        self.rng_state()
+    }
 
-        // Pick a number of bytes to overwrite
-        let overwrite_num = (self.rand() % ceiling) + 1;
+    /// Picks a new seed derived from the current RNG state rather than fresh
+    /// entropy, this breaks the Mutator out of its current stream while two
+    /// Mutators with the same state still end up with the same new seed
+    #[allow(dead_code)]
+    pub fn reseed_deterministic(&mut self) -> usize {
+        self.rng = sanitize_state(splitmix64(self.rng as u64) as usize);
+        self.rng
+    }
 
-        // Divide that number by 8 to determine how many u64s will fit
-        let num_u64 = overwrite_num / 8;
+    /// Derives a seed for a forked child and advances our own state, so each
+    /// child in a forkserver gets its own stream instead of replaying ours.
+    /// The seeds are reproducible from the parent's starting state and are
+    /// mixed so a child's stream doesn't line up with the parent's
+    pub fn advance_for_child(&mut self) -> usize {
+        let draw = self.rand();
+        sanitize_state(splitmix64(draw as u64) as usize)
+    }
 
-        // Make sure we don't go out of bounds
-        let max_overwrite = self.input.len() - 8;
+    /// Retrieves the raw RNG state, this can be handed back to `set_rng_state`
+    /// later to replay the exact same stream of random values
+    pub fn rng_state(&self) -> usize {
+        self.rng
+    }
 
-        // Overwrite up to num_u64 u64 values
-        for _ in 0..num_u64 {
-            // Pick an index to overwrite at
-            let idx = self.rand() % (max_overwrite + 1);
+    /// Sets the raw RNG state directly, a zero state is replaced since it would
+    /// leave xorshift stuck at zero
+    pub fn set_rng_state(&mut self, state: usize) {
+        self.rng = sanitize_state(state);
+    }
 
-            // Pick a magic value
-            let magic = MAGIC_NUMBERS[self.rand() % MAGIC_NUMBERS.len()];
+    /// Xorshift pseudo-random function based on Brandon Falk's streams
+    #[inline]
+    fn rand(&mut self) -> usize {
+        xorshift(&mut self.rng)
+    }
 
-            // Randomly corrupt the magic number
-            let magic_bytes = if self.rand() % 2 == 0 {
-                self.mutate_magic(magic)
-            } else {
-                magic.to_ne_bytes().to_vec()
-            };
+    /// Fills `out` with a batch of RNG draws, this is the same stream that
+    /// calling `rand` once per slot would produce, just without the per-call
+    /// overhead in hot loops
+    #[allow(dead_code)]
+    pub fn fill_randoms(&mut self, out: &mut [usize]) {
+        core_mutations::fill_randoms(&mut self.rng, out);
+    }
 
-            // Overwrite with magic bytes
-            for (i, &byte) in magic_bytes.iter().enumerate() {
-                self.input[idx + i] = byte;
-            }
+    /// Insert bytes into the input randomly
+    fn byte_insert(&mut self) {
+        core_mutations::byte_insert(
+            &mut self.input,
+            &mut self.rng,
+            self.max_size,
+            &self.alphabet,
+        );
+    }
+
+    /// Overwrite bytes in the input randomly
+    fn byte_overwrite(&mut self) {
+        core_mutations::byte_overwrite(&mut self.input, &mut self.rng, &self.alphabet);
+    }
+
+    /// Delete bytes in the input randomly
+    fn byte_delete(&mut self) {
+        core_mutations::byte_delete(&mut self.input, &mut self.rng);
+    }
+
+    /// Grabs a block from the input, and insert it randomly somewhere else
+    fn block_insert(&mut self) {
+        core_mutations::block_insert(&mut self.input, &mut self.rng, self.max_size);
+    }
+
+    /// Grabs a block from the input and copy it over to another location
+    fn block_overwrite(&mut self) {
+        core_mutations::block_overwrite(&mut self.input, &mut self.rng);
+    }
+
+    /// Removes a random block from the input buffer
+    fn block_delete(&mut self) {
+        core_mutations::block_delete(&mut self.input, &mut self.rng);
+    }
+
+    /// Picks two equal-length, non-overlapping blocks in the input and swaps
+    /// them in place, the length of the input is preserved
+    fn swap(&mut self) {
+        core_mutations::swap(&mut self.input, &mut self.rng);
+    }
+
+    /// Swaps a few random pairs of adjacent bytes, parsers that are sensitive
+    /// to ordering tend to trip over these
+    fn transpose(&mut self) {
+        core_mutations::transpose(&mut self.input, &mut self.rng);
+    }
+
+    /// Removes one contiguous range of up to `delete_range_fraction` of the
+    /// input, unlike `block_delete` this isn't capped at a fixed block size.
+    /// The range never cuts the input below `min_size`
+    fn delete_range(&mut self) {
+        let len = self.input.len();
+        if len == 0 {
+            return;
         }
+
+        let spare = len.saturating_sub(self.config.min_size);
+        let fraction = self
+            .config
+            .delete_range_fraction
+            .unwrap_or(DELETE_RANGE_FRACTION)
+            .min(spare as f32 / len as f32);
+
+        core_mutations::delete_range(&mut self.input, &mut self.rng, fraction);
     }
 
-    /// Splices two inputs together if possible, this strategy depends on
-    /// having access to the corpus in order to select a 2nd input
-    fn splice(&mut self, corpus: &Corpus) {
-        // Take a block of the current input
-        let old_block_start = self.rand() % self.input.len();
+    /// Toggles the case of the ASCII letters in a random block of the input,
+    /// case-insensitive keyword handling (HTTP methods, SQL) is a common source
+    /// of bugs
+    fn case_flip(&mut self) {
+        core_mutations::case_flip(&mut self.input, &mut self.rng);
+    }
 
-        // Pick a length for the block
-        let old_block_len = self.rand() % (self.input.len() - old_block_start) + 1;
+    /// Reverses a random block of the input in place
+    fn reverse(&mut self) {
+        core_mutations::reverse(&mut self.input, &mut self.rng);
+    }
 
-        // Pick a new input index
-        let new_idx = self.rand() % corpus.num_inputs();
+    /// Rotates a random block of the input left or right by a random amount
+    fn rotate_bytes(&mut self) {
+        core_mutations::rotate_bytes(&mut self.input, &mut self.rng);
+    }
 
-        // Get reference to new input
-        let Some(new_input) = corpus.get_input(new_idx) else {
-            return; // No inputs in corpus?
+    /// Splits the input into records on the configured delimiter, the records
+    /// don't include the delimiter. Also returns whether or not the input
+    /// ended with a delimiter so that it can be restored when joining
+    fn split_records(&self, delim: u8) -> (Vec<Vec<u8>>, bool) {
+        let trailing = self.input.last() == Some(&delim);
+        let body = if trailing {
+            &self.input[..self.input.len() - 1]
+        } else {
+            &self.input[..]
         };
 
-        // Determine the slack space left
-        let slack = self.max_size - old_block_len;
+        let records = body
+            .split(|&byte| byte == delim)
+            .map(|record| record.to_vec())
+            .collect();
 
-        // If there's no slack, we can return early
-        if slack == 0 {
+        (records, trailing)
+    }
+
+    /// Rebuilds the input buffer from records by joining them on the delimiter
+    fn join_records(&mut self, records: Vec<Vec<u8>>, delim: u8, trailing: bool) {
+        self.input = records.join(&delim);
+        if trailing {
+            self.input.push(delim);
+        }
+    }
+
+    /// Shuffles the order of the records in the input
+    fn record_shuffle(&mut self) {
+        let Some(delim) = self.config.record_delimiter else {
+            return;
+        };
+
+        // We need at least two records to shuffle
+        let (mut records, trailing) = self.split_records(delim);
+        if records.len() < 2 {
             return;
         }
 
-        // Pick a place in the new input to read a block from
-        let new_block_start = self.rand() % new_input.len();
+        // Fisher-Yates shuffle
+        for i in (1..records.len()).rev() {
+            let j = self.rand() % (i + 1);
+            records.swap(i, j);
+        }
 
-        // Pick a length ceiling of the new block, guaranteed to be at least 1
-        let new_ceiling = std::cmp::min(new_input.len() - new_block_start, slack);
+        self.join_records(records, delim, trailing);
+    }
 
-        // Pick a length
-        let new_block_len = (self.rand() % new_ceiling) + 1;
+    /// Duplicates a random record, the copy is placed right after the original
+    fn record_duplicate(&mut self) {
+        let Some(delim) = self.config.record_delimiter else {
+            return;
+        };
 
-        // Determine total length we'll have
-        let total_len = old_block_len + new_block_len;
+        // Pick a record to duplicate
+        let (mut records, trailing) = self.split_records(delim);
+        let idx = self.rand() % records.len();
 
-        // Adjust input buffer if necessary
-        if total_len > self.input.len() {
-            self.input.resize(total_len, 0);
+        // Make sure we have the slack for the record and another delimiter
+        let slack = self.max_size.saturating_sub(self.input.len());
+        if records[idx].len() + 1 > slack {
+            return;
         }
 
-        // Copy with memmove because of overlap potential
-        self.input
-            .copy_within(old_block_start..old_block_start + old_block_len, 0);
+        let record = records[idx].clone();
+        records.insert(idx + 1, record);
 
-        // Then, copy the new block right after the old block
-        let new_block = &new_input[new_block_start..new_block_start + new_block_len];
-        self.input[old_block_len..total_len].copy_from_slice(new_block);
+        self.join_records(records, delim, trailing);
+    }
 
-        // Adjust input buffer length if necessary
-        if total_len < self.input.len() {
-            self.input.truncate(total_len);
+    /// Deletes a random record, always leaves at least one
+    fn record_delete(&mut self) {
+        let Some(delim) = self.config.record_delimiter else {
+            return;
+        };
+
+        // We need at least two records to delete one
+        let (mut records, trailing) = self.split_records(delim);
+        if records.len() < 2 {
+            return;
+        }
+
+        let idx = self.rand() % records.len();
+        records.remove(idx);
+
+        // Deleting the only non-empty record would leave nothing behind
+        if records.len() == 1 && records[0].is_empty() && !trailing {
+            return;
         }
+
+        self.join_records(records, delim, trailing);
     }
 
-    /// The main mutation function which will:
-    /// 1. Clear the current input buffer
-    /// 2. Randomly select an input from the corpus or generate one from scratch
-    /// 3. Select the number of mutation rounds (stack)
-    /// 4. Randomly select mutation strategies and apply them for n rounds
-    pub fn mutate_input(&mut self, corpus: &Corpus) {
-        // Clear current input
+    /// Generates a random input from scratch, not likely to be a great strategy.
+    /// The bytes are filled a whole RNG draw at a time rather than one byte
+    /// per draw, which is roughly 8x fewer draws and avoids zero-filling the
+    /// buffer first, so multi-megabyte inputs are cheap. This means a given
+    /// seed generates different bytes than the old byte-per-draw fill did
+    fn generate_random_input(&mut self) {
+        const DRAW_BYTES: usize = std::mem::size_of::<usize>();
+
+        // Pick a size for the input
+        let input_size = (self.rand() % self.max_size) + 1;
+
+        // Start from an empty buffer with enough room
         self.input.clear();
-        self.last_mutation.clear();
+        self.input.reserve(input_size);
+
+        // Fill in the data randomly, the last draw may only be partially used
+        while self.input.len() < input_size {
+            let remaining = std::cmp::min(input_size - self.input.len(), DRAW_BYTES);
+            let draw = self.rand().to_le_bytes();
+            for &byte in &draw[..remaining] {
+                let byte = core_mutations::remap_byte(byte, &self.alphabet);
+                self.input.push(byte);
+            }
+        }
+    }
 
-        // Get the number of inputs to choose from
-        let num_inputs = corpus.num_inputs();
+    /// Inserts a run of 0x00 bytes, parsers tend to mishandle these as string
+    /// terminators
+    fn insert_null_run(&mut self) {
+        core_mutations::insert_run(&mut self.input, &mut self.rng, self.max_size, 0x00);
+    }
 
-        // n% of the time, just generate a new input from scratch
-        let gen = self.rand() % 100;
+    /// Inserts a run of 0xFF bytes, parsers tend to mishandle these when they
+    /// get sign extended
+    fn insert_high_run(&mut self) {
+        core_mutations::insert_run(&mut self.input, &mut self.rng, self.max_size, 0xFF);
+    }
 
-        // If we don't have any inputs to choose from, create a random one
-        if num_inputs == 0 || gen < GEN_SCRATCH_RATE {
-            self.generate_random_input();
+    /// Replaces each byte of the input with probability `scatter_prob`
+    fn scatter(&mut self) {
+        let prob = self.config.scatter_prob;
+        core_mutations::scatter(&mut self.input, &mut self.rng, prob, &self.alphabet);
+    }
+
+    /// Copies a random block of the input over another region of the input
+    fn copy_within(&mut self) {
+        core_mutations::copy_within(&mut self.input, &mut self.rng);
+    }
+
+    /// Generates an input from scratch by stringing together random dictionary
+    /// tokens, the last token is cut short if it doesn't fit in max_size
+    fn generate_dictionary_input(&mut self) {
+        // Pick a size for the input
+        let input_size = (self.rand() % self.max_size) + 1;
+
+        // Append tokens until we reach the size
+        self.input.clear();
+        while self.input.len() < input_size {
+            let idx = self.rand() % self.dictionary.len();
+            let token = &self.dictionary[idx].bytes;
+            let len = std::cmp::min(token.len(), self.max_size - self.input.len());
+            self.input.extend_from_slice(&token[..len]);
+        }
+    }
+
+    /// Randomly flips bits in the input buffer
+    fn bit_flip(&mut self) {
+        if self.config.distinct_bit_flips {
+            core_mutations::bit_flip_distinct(&mut self.input, &mut self.rng);
             return;
         }
 
-        // Pick an input from the corpus to use
-        let idx = self.rand() % num_inputs;
+        core_mutations::bit_flip(&mut self.input, &mut self.rng);
+    }
 
-        // Get the input
-        let chosen = corpus.get_input(idx).unwrap();
+    /// Inserts a random byte block into the input buffer
+    fn grow(&mut self) {
+        if let Some(dist) = self.config.target_size {
+            self.resize_toward(dist);
+            return;
+        }
 
-        // Copy the input over
-        self.input.extend_from_slice(chosen);
+        core_mutations::grow(
+            &mut self.input,
+            &mut self.rng,
+            self.max_size,
+            &self.alphabet,
+        );
+    }
 
-        // We have an input, pick a number of rounds of mutation
-        let rounds = (self.rand() % MAX_STACK) + 1;
+    /// Truncates the input a random amount of bytes but always leaves at least
+    /// one byte
+    fn truncate(&mut self) {
+        if let Some(dist) = self.config.target_size {
+            self.resize_toward(dist);
+            return;
+        }
 
-        // Apply mutations for number of rounds
-        for _ in 0..rounds {
-            // Determine the pool of candidates, we don't want to frequently
-            // use longshot strategies
-            let longshot = self.rand() % 100;
+        if !self.config.record_boundaries.is_empty() {
+            // The boundaries are offsets into the whole input, rebase them
+            // onto the window we're mutating
+            let boundaries: Vec<usize> = self
+                .config
+                .record_boundaries
+                .iter()
+                .filter_map(|boundary| boundary.checked_sub(self.window_start))
+                .collect();
+
+            core_mutations::truncate_to_boundary(&mut self.input, &mut self.rng, &boundaries);
+            return;
+        }
 
-            // If we're within the longshot range, add them to the possible
-            let pool = if longshot <= LONGSHOT_MUTATION_RATE {
-                MUTATIONS.len()
-            } else {
-                MUTATIONS.len() - 3
-            };
+        core_mutations::truncate(&mut self.input, &mut self.rng);
+    }
 
-            // Pick mutation type
-            let mutation_idx = self.rand() % pool;
+    /// Samples a target size from `dist` and grows or truncates the input to
+    /// exactly that size, growing inserts a run of one random byte like `grow`
+    /// does. Under a preserved prefix or focus region the target applies to
+    /// the mutable window rather than the whole input
+    fn resize_toward(&mut self, dist: SizeDist) {
+        // An empty mutable window (ie a zero-length focus) has nowhere to grow
+        // from, and nothing to truncate
+        let len = self.input.len();
+        if len == 0 {
+            return;
+        }
 
-            // Match on the mutation and apply it
-            match MUTATIONS[mutation_idx] {
-                MutationTypes::ByteInsert => {
-                    self.byte_insert();
-                    self.last_mutation.push(MutationTypes::ByteInsert);
-                }
-                MutationTypes::ByteOverwrite => {
-                    self.byte_overwrite();
-                    self.last_mutation.push(MutationTypes::ByteOverwrite);
-                }
-                MutationTypes::ByteDelete => {
-                    self.byte_delete();
-                    self.last_mutation.push(MutationTypes::ByteDelete);
-                }
-                MutationTypes::BlockInsert => {
-                    self.block_insert();
-                    self.last_mutation.push(MutationTypes::BlockInsert);
-                }
-                MutationTypes::BlockOverwrite => {
-                    self.block_overwrite();
-                    self.last_mutation.push(MutationTypes::BlockOverwrite);
-                }
-                MutationTypes::BlockDelete => {
-                    self.block_delete();
-                    self.last_mutation.push(MutationTypes::BlockDelete);
-                }
-                MutationTypes::BitFlip => {
-                    self.bit_flip();
-                    self.last_mutation.push(MutationTypes::BitFlip);
-                }
-                MutationTypes::Grow => {
-                    self.grow();
-                    self.last_mutation.push(MutationTypes::Grow);
-                }
-                MutationTypes::Truncate => {
-                    self.truncate();
-                    self.last_mutation.push(MutationTypes::Truncate);
-                }
-                MutationTypes::MagicByteInsert => {
-                    self.magic_byte_insert();
-                    self.last_mutation.push(MutationTypes::MagicByteInsert);
-                }
-                MutationTypes::MagicByteOverwrite => {
-                    self.magic_byte_overwrite();
-                    self.last_mutation.push(MutationTypes::MagicByteOverwrite);
-                }
-                MutationTypes::Splice => {
-                    self.splice(corpus);
-                    self.last_mutation.push(MutationTypes::Splice);
-                }
-            }
+        // Sample a size we're able to reach
+        let SizeDist::Uniform { min, max } = dist;
+        let (min, max) = (min.max(1), max.min(self.max_size));
+        if min > max {
+            return;
         }
+        let target = min + self.rand() % (max - min + 1);
+
+        if target < len {
+            self.input.truncate(target);
+        } else if target > len {
+            let idx = self.rand() % len;
+            let byte = core_mutations::remap_byte(self.rand() as u8, &self.alphabet);
+            self.input
+                .splice(idx..idx, std::iter::repeat_n(byte, target - len));
+        }
+    }
 
-        // This isn't prod
-        assert!(!self.input.is_empty());
-        assert!(self.input.len() <= self.max_size);
+    /// The number of bytes a magic value is emitted as when it isn't randomly
+    /// truncated, a configured `magic_width` is clamped to the size of a u64
+    fn magic_width(&self) -> usize {
+        self.config.magic_width.map_or(8, |width| width.clamp(1, 8))
     }
 
-    /// Clears the current mutator input buffer and copies a passed in slice
-    /// into the input buffer
-    pub fn memcpy_input(&mut self, slice: &[u8]) {
-        // Clear the current input
-        self.input.clear();
+    /// Takes a magic number value and mutates it
+    fn mutate_magic(&mut self, magic: u64) -> Vec<u8> {
+        // Mutate the magic value
+        let magic = match self.rand() % 14 {
+            0 => magic,
+            1 => magic & 0xFF,
+            2 => magic & 0xFFFF,
+            3 => magic & 0xFFFFFFFF,
+            4 => magic.wrapping_sub(1),
+            5 => magic.wrapping_add(1),
+            6 => !magic,                 // Bitwise NOT
+            7 => magic << 1,             // Left shift by 1
+            8 => magic >> 1,             // Right shift by 1
+            9 => magic.rotate_left(8),   // Rotate left by 8 bits
+            10 => magic.rotate_right(8), // Rotate right by 8 bits
+            11 => magic ^ 0xFFFFFFFF,    // XOR with all 1s (32-bit)
+            12 => magic.swap_bytes(),    // Swap byte order
+            13 => {
+                // Flip a random bit
+                let bit = self.rand() % 64;
+                magic ^ (1 << bit)
+            }
+            _ => unreachable!(),
+        };
 
-        // Copy the passed in buffer
-        self.input.extend_from_slice(slice);
+        // Convert to bytes
+        let magic_bytes = magic.to_le_bytes();
+
+        // If we have a fixed width, don't randomly truncate, if we have width
+        // weights take an aligned chunk of a weighted width
+        let mut chunk = if self.config.magic_width.is_some() {
+            magic_bytes[..self.magic_width()].to_vec()
+        } else if let Some(width) = self.weighted_magic_width() {
+            let start = (self.rand() % (8 / width)) * width;
+            magic_bytes[start..start + width].to_vec()
+        } else {
+            self.truncate_magic(magic_bytes)
+        };
+
+        // The chunk is little-endian, flip it if we want big-endian
+        if self.pick_endianness() == Endianness::Big {
+            chunk.reverse();
+        }
+
+        chunk
+    }
+
+    /// Randomly truncates the little-endian bytes of a magic value to a u32,
+    /// u16, or u8 sized chunk (or leaves all of them)
+    fn truncate_magic(&mut self, magic_bytes: [u8; 8]) -> Vec<u8> {
+        match self.rand() % 15 {
+            0 => magic_bytes.to_vec(),       // All 8 bytes (u64)
+            1 => magic_bytes[0..4].to_vec(), // First 4 bytes (u32)
+            2 => magic_bytes[4..8].to_vec(), // Last 4 bytes (u32)
+            3 => magic_bytes[0..2].to_vec(), // First 2 bytes (u16)
+            4 => magic_bytes[2..4].to_vec(), // Second 2 bytes (u16)
+            5 => magic_bytes[4..6].to_vec(), // Third 2 bytes (u16)
+            6 => magic_bytes[6..8].to_vec(), // Last 2 bytes (u16)
+            7 => vec![magic_bytes[0]],       // 1st byte (u8)
+            8 => vec![magic_bytes[1]],       // 2nd byte (u8)
+            9 => vec![magic_bytes[2]],       // 3rd byte (u8)
+            10 => vec![magic_bytes[3]],      // 4th byte (u8)
+            11 => vec![magic_bytes[4]],      // 5th byte (u8)
+            12 => vec![magic_bytes[5]],      // 6th byte (u8)
+            13 => vec![magic_bytes[6]],      // 7th byte (u8)
+            14 => vec![magic_bytes[7]],      // 8th byte (u8)
+            _ => unreachable!(),
+        }
+    }
+
+    /// Picks a magic value width of 1, 2, 4, or 8 bytes according to the
+    /// configured `magic_width_weights`, None if there are no weights or they
+    /// are all zero
+    fn weighted_magic_width(&mut self) -> Option<usize> {
+        const WIDTHS: [usize; 4] = [1, 2, 4, 8];
+
+        let weights = self.config.magic_width_weights?;
+        let total: usize = weights.iter().sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut roll = self.rand() % total;
+        for (width, weight) in WIDTHS.iter().zip(weights) {
+            if roll < weight {
+                return Some(*width);
+            }
+            roll -= weight;
+        }
+
+        None
+    }
+
+    /// Picks the byte order for a magic value, randomly unless one is forced
+    /// through the config so length fields in either order get exercised
+    fn pick_endianness(&mut self) -> Endianness {
+        if let Some(endianness) = self.config.magic_endianness {
+            return endianness;
+        }
+
+        if self.rand().is_multiple_of(2) {
+            Endianness::Little
+        } else {
+            Endianness::Big
+        }
+    }
+
+    /// Emits an unmutated magic value at the configured width, or a weighted
+    /// one, in either byte order
+    fn encode_magic(&mut self, magic: u64) -> Vec<u8> {
+        let width = match self.config.magic_width {
+            Some(_) => self.magic_width(),
+            None => self.weighted_magic_width().unwrap_or(8),
+        };
+
+        let mut bytes = magic.to_le_bytes()[..width].to_vec();
+        if self.pick_endianness() == Endianness::Big {
+            bytes.reverse();
+        }
+
+        bytes
+    }
+
+    /// Inserts magic bytes into the input buffer after optionally mutating
+    /// the bytes
+    fn magic_byte_insert(&mut self) {
+        // Defaults to global max, but can be hand tuned
+        const MAX_INSERTS: usize = MAX_BYTE_CORRUPTION;
+
+        // Determine the slack space we have
+        let slack = self.max_size.saturating_sub(self.input.len());
+
+        // If we don't have any slack space, return
+        if slack == 0 {
+            return;
+        }
+
+        // Determine the ceiling
+        let ceiling = std::cmp::min(slack, MAX_INSERTS);
+
+        // Pick number of bytes to insert, at least 1
+        let insert_num = (self.rand() % ceiling) + 1;
+
+        // Divide that by 8 to determine how many u64s will fit
+        let num_u64 = insert_num / 8;
+
+        // Insert up to num_u64 u64 values, likely much smaller
+        for _ in 0..num_u64 {
+            // Pick an index to insert at
+            let idx = self.rand() % self.input.len();
+
+            // Pick a magic value
+            let magic_idx = self.rand() % self.magic_numbers().len();
+            let magic = self.magic_numbers()[magic_idx];
+
+            // Randomly corrupt the magic number
+            let magic_bytes = if self.rand() % 2 == 0 {
+                self.mutate_magic(magic)
+            } else {
+                self.encode_magic(magic)
+            };
+
+            // Insert magic bytes
+            for (i, &byte) in magic_bytes.iter().enumerate() {
+                self.input.insert(idx + i, byte);
+            }
+        }
+    }
+
+    /// Overwrites randomly selected input buffer data with magic bytes that are
+    /// optionally mutated
+    fn magic_byte_overwrite(&mut self) {
+        // If the input isn't at least 8 bytes, just NOP
+        if self.input.len() < 8 {
+            return;
+        }
+
+        // Defaults to global max, but can be hand tuned
+        const MAX_OVERWRITES: usize = MAX_BYTE_CORRUPTION;
+
+        // Determine how many bytes we can overwrite
+        let ceiling = std::cmp::min(self.input.len(), MAX_OVERWRITES);
+
+        // Pick a number of bytes to overwrite
+        let overwrite_num = (self.rand() % ceiling) + 1;
+
+        // Divide that number by 8 to determine how many u64s will fit
+        let num_u64 = overwrite_num / 8;
+
+        // Make sure we don't go out of bounds
+        let max_overwrite = self.input.len() - 8;
+
+        // Overwrite up to num_u64 u64 values
+        for _ in 0..num_u64 {
+            // Pick an index to overwrite at
+            let idx = self.rand() % (max_overwrite + 1);
+
+            // Pick a magic value
+            let magic_idx = self.rand() % self.magic_numbers().len();
+            let magic = self.magic_numbers()[magic_idx];
+
+            // Randomly corrupt the magic number
+            let magic_bytes = if self.rand() % 2 == 0 {
+                self.mutate_magic(magic)
+            } else {
+                self.encode_magic(magic)
+            };
+
+            // Overwrite with magic bytes
+            for (i, &byte) in magic_bytes.iter().enumerate() {
+                self.input[idx + i] = byte;
+            }
+        }
+    }
+
+    /// Places a dictionary token into the input, tokens with a preferred offset
+    /// land there most of the time and anywhere else otherwise. The token
+    /// overwrites what was there and may extend the input, up to max_size
+    fn dict_place(&mut self) {
+        // Nothing to place without a dictionary
+        if self.dictionary.is_empty() {
+            return;
+        }
+
+        // Pick a token
+        let idx = self.rand() % self.dictionary.len();
+        let token = self.dictionary[idx].clone();
+
+        // Pick where to place it, a preferred offset past the end of the input
+        // just appends the token. The offset is into the whole input, so one
+        // in front of the window we're mutating can't be honored
+        let preferred = token
+            .preferred_offset
+            .and_then(|offset| offset.checked_sub(self.window_start));
+        let offset = match preferred {
+            Some(offset) if self.rand() % 100 < DICT_PREFERRED_OFFSET_RATE => {
+                std::cmp::min(offset, self.input.len())
+            }
+            _ => self.rand() % self.input.len(),
+        };
+
+        // Clamp the token to the space we have before max_size
+        let len = std::cmp::min(token.bytes.len(), self.max_size.saturating_sub(offset));
+        if len == 0 {
+            return;
+        }
+
+        // Overwrite what overlaps with the input and extend with the rest
+        let overlap = std::cmp::min(len, self.input.len() - offset);
+        self.input[offset..offset + overlap].copy_from_slice(&token.bytes[..overlap]);
+        self.input.extend_from_slice(&token.bytes[overlap..len]);
+    }
+
+    /// Inserts one of the learned byte pairs at a random offset, pairs are
+    /// picked proportionally to how often they appeared in the corpus
+    fn bigram_insert(&mut self) {
+        // Nothing to insert without a table or room for a pair
+        if self.bigrams.is_empty() || self.max_size.saturating_sub(self.input.len()) < 2 {
+            return;
+        }
+
+        // Roll against the total count and find the pair it lands on
+        let total: usize = self.bigrams.iter().map(|(_, count)| count).sum();
+        let mut roll = self.rand() % total;
+        let mut pair = self.bigrams[0].0;
+        for &(bigram, count) in self.bigrams.iter() {
+            if roll < count {
+                pair = bigram;
+                break;
+            }
+            roll -= count;
+        }
+
+        // Pick where to insert it, the end of the input is fair game
+        let offset = self.rand() % (self.input.len() + 1);
+        self.input.splice(offset..offset, pair);
+    }
+
+    /// Overwrites a declared field with a boundary value for its kind, ie for
+    /// an unsigned field: 0, 1, max, or the field's current value +/- 1. A
+    /// length field also tries the number of bytes that follow it. Fields that
+    /// don't fit in the window we're mutating are left alone
+    fn field_flip(&mut self) {
+        // Nothing to flip without fields
+        if self.fields.is_empty() {
+            return;
+        }
+
+        // Pick a field, its offset is into the whole input so translate it
+        // into the window
+        let idx = self.rand() % self.fields.len();
+        let field = self.fields[idx];
+        let Some(start) = field.offset.checked_sub(self.window_start) else {
+            return;
+        };
+        let end = start + field.width;
+        if end > self.input.len() {
+            return;
+        }
+
+        // Read the current value
+        let mut bytes = [0u8; 8];
+        bytes[..field.width].copy_from_slice(&self.input[start..end]);
+        let actual = u64::from_le_bytes(bytes);
+
+        // Determine the boundary values for the field's width
+        let bits = field.width * 8;
+        let mask = u64::MAX >> (64 - bits);
+        let sign_bit = 1u64 << (bits - 1);
+
+        let mut candidates = vec![
+            0,
+            1,
+            mask,
+            actual.wrapping_sub(1) & mask,
+            actual.wrapping_add(1) & mask,
+        ];
+
+        match field.kind {
+            FieldKind::Unsigned => (),
+            FieldKind::Signed => {
+                candidates.push(sign_bit); // Minimum
+                candidates.push(sign_bit - 1); // Maximum
+            }
+            FieldKind::Length => {
+                let remaining = (self.input.len() - end + self.window_tail) as u64;
+                candidates.push(remaining & mask);
+                candidates.push(remaining.wrapping_add(1) & mask);
+            }
+        }
+
+        // Write it back
+        let value = candidates[self.rand() % candidates.len()];
+        self.input[start..end].copy_from_slice(&value.to_le_bytes()[..field.width]);
+    }
+
+    /// Selects a splice donor by content rather than by position in the corpus
+    /// using rendezvous hashing: each input's content hash is combined with a
+    /// single RNG draw and the highest score wins. Given the same set of donor
+    /// contents, a replay picks the same donor even if the corpus was loaded in
+    /// a different order. This does *not* hold if the contents of the corpus
+    /// change, adding or removing any input can change which donor wins
+    fn stable_donor(&mut self, corpus: &dyn CorpusView) -> usize {
+        let draw = self.rand() as u64;
+
+        (0..corpus.num_inputs())
+            .max_by_key(|&idx| {
+                let mut hasher = DefaultHasher::new();
+                (corpus.content_hash(idx), draw).hash(&mut hasher);
+                hasher.finish()
+            })
+            .unwrap_or(0)
+    }
+
+    /// Picks a splice donor with the same tag as the corpus input we were
+    /// mutated from, None if tag-aware splicing is off or there's no tag
+    fn tagged_donor(&mut self, corpus: &dyn CorpusView) -> Option<usize> {
+        if !self.config.tag_aware_splice {
+            return None;
+        }
+
+        let tag = corpus.tag(self.last_parent?)?;
+        let draw = self.rand();
+        corpus.select_tagged(tag, draw)
+    }
+
+    /// Splices two inputs together if possible, this strategy depends on
+    /// having access to the corpus in order to select a 2nd input
+    ///
+    /// The result is always a block of the current (recipient) input followed
+    /// by a block of the donor input. The recipient block is moved to the
+    /// front of the buffer and everything else of the recipient is discarded,
+    /// by default the recipient block is chosen from anywhere in the input, if
+    /// `splice_keep_recipient_head` is set it always starts at offset 0. The
+    /// donor block is clamped to whatever slack remains after the recipient
+    /// block, so large donors are cut short rather than exceeding max_size.
+    /// `recipient` is the corpus index the input came from, if
+    /// `splice_exclude_self` is set the donor is re-rolled to avoid it
+    fn splice(&mut self, corpus: &dyn CorpusView, recipient: Option<usize>) {
+        // Take a block of the current input
+        let old_block_start = if self.config.splice_keep_recipient_head {
+            0
+        } else {
+            self.rand() % self.input.len()
+        };
+
+        // Pick a length for the block
+        let old_block_len = self.rand() % (self.input.len() - old_block_start) + 1;
+
+        // Pick a new input index, if we're tag-aware we prefer a donor in the
+        // same format as our input, and if the corpus has an n-gram index we
+        // prefer a donor that shares byte patterns with our input
+        let mut new_idx = if let Some(idx) = self.tagged_donor(corpus) {
+            idx
+        } else if self.config.stable_splice_donors {
+            self.stable_donor(corpus)
+        } else if let Some(idx) =
+            corpus.splice_compatible(&self.input, &mut || xorshift(&mut self.rng))
+        {
+            idx
+        } else {
+            self.rand() % corpus.num_inputs()
+        };
+
+        // Splicing an input with itself is mostly noise, re-roll the donor
+        // uniformly from the other inputs if there are any
+        let num_inputs = corpus.num_inputs();
+        if self.config.splice_exclude_self && recipient == Some(new_idx) && num_inputs > 1 {
+            new_idx = (new_idx + 1 + self.rand() % (num_inputs - 1)) % num_inputs;
+        }
+
+        // Get reference to new input
+        let Some(new_input) = corpus.get_input(new_idx) else {
+            return; // No inputs in corpus?
+        };
+
+        // Nothing to graft from an empty donor
+        if new_input.is_empty() {
+            return;
+        }
+
+        // Determine the slack space left
+        let slack = self.max_size.saturating_sub(old_block_len);
+
+        // If there's no slack, we can return early
+        if slack == 0 {
+            return;
+        }
+
+        // Pick a place in the new input to read a block from
+        let new_block_start = self.rand() % new_input.len();
+
+        // Pick a length ceiling of the new block, guaranteed to be at least 1
+        let new_ceiling = std::cmp::min(new_input.len() - new_block_start, slack);
+
+        // Pick a length, optionally biased towards larger grafts by taking the
+        // larger of two draws
+        let mut new_block_len = (self.rand() % new_ceiling) + 1;
+        if self.config.large_splice_bias {
+            new_block_len = std::cmp::max(new_block_len, (self.rand() % new_ceiling) + 1);
+        }
+
+        // Determine total length we'll have
+        let total_len = old_block_len + new_block_len;
+
+        // Adjust input buffer if necessary
+        if total_len > self.input.len() {
+            self.input.resize(total_len, 0);
+        }
+
+        // Copy with memmove because of overlap potential
+        self.input
+            .copy_within(old_block_start..old_block_start + old_block_len, 0);
+
+        // Then, copy the new block right after the old block
+        let new_block = &new_input[new_block_start..new_block_start + new_block_len];
+        self.input[old_block_len..total_len].copy_from_slice(new_block);
+
+        // Adjust input buffer length if necessary
+        if total_len < self.input.len() {
+            self.input.truncate(total_len);
+        }
+    }
+
+    /// Appends a whole input from the corpus to the current input, the donor is
+    /// truncated to whatever slack we have left before max_size
+    fn concat(&mut self, corpus: &dyn CorpusView) {
+        // Determine the slack space we have
+        let slack = self.max_size.saturating_sub(self.input.len());
+
+        // If we don't have any slack or any donors, return
+        if slack == 0 || corpus.num_inputs() == 0 {
+            return;
+        }
+
+        // Pick a donor
+        let idx = self.rand() % corpus.num_inputs();
+        let Some(donor) = corpus.get_input(idx) else {
+            return;
+        };
+
+        // Append as much of it as fits
+        let len = std::cmp::min(donor.len(), slack);
+        self.input.extend_from_slice(&donor[..len]);
+    }
+
+    /// Determines whether or not a mutation strategy can be selected at all
+    /// given the current configuration, some strategies are opt-in only
+    fn is_selectable(&self, mutation: MutationTypes) -> bool {
+        // Strategies can be turned off entirely
+        if self.disabled & (1 << mutation as u64) != 0 || self.weight(mutation) == 0 {
+            return false;
+        }
+
+        // A focus region only allows strategies that won't shift the bytes
+        // outside of the region around
+        if self.config.focus.is_some() && !mutation.is_length_preserving() {
+            return false;
+        }
+
+        // An input at max_size has no slack, so strategies that only grow it
+        // would NOP and waste the round, fixed-size formats land here a lot
+        if !self.config.grow_at_capacity
+            && self.input.len() >= self.max_size
+            && mutation.is_growth()
+        {
+            return false;
+        }
+
+        match mutation {
+            MutationTypes::Utf8Aware => self.config.utf8_aware,
+            MutationTypes::CaseFlip => self.config.case_flip,
+            MutationTypes::RecordShuffle
+            | MutationTypes::RecordDuplicate
+            | MutationTypes::RecordDelete => self.config.record_delimiter.is_some(),
+            MutationTypes::DictPlace => !self.dictionary.is_empty(),
+            MutationTypes::FieldFlip => !self.fields.is_empty(),
+            MutationTypes::BigramInsert => !self.bigrams.is_empty(),
+            MutationTypes::Scatter => self.config.scatter_prob > 0.0,
+            _ => true,
+        }
+    }
+
+    /// Iterates over the mutation strategies that are currently candidates for
+    /// selection, longshots are only included if `allow_longshots` is set
+    fn candidates(&self, allow_longshots: bool) -> impl Iterator<Item = MutationTypes> + '_ {
+        MUTATIONS
+            .iter()
+            .copied()
+            .filter(move |&m| self.is_selectable(m) && (allow_longshots || !self.is_longshot(m)))
+    }
+
+    /// Picks a random valid codepoint, mostly printable ASCII since that is
+    /// what text parsers tend to branch on
+    fn random_char(&mut self) -> char {
+        if self.rand().is_multiple_of(2) {
+            return (0x20 + (self.rand() % 0x5F) as u8) as char;
+        }
+
+        char::from_u32((self.rand() % 0x110000) as u32).unwrap_or('?')
+    }
+
+    /// Mutates the input at codepoint boundaries so that the buffer remains
+    /// valid UTF-8: inserts, deletes, or replaces a whole codepoint, or flips
+    /// the case of one. If the input isn't valid UTF-8 this is a NOP
+    fn utf8_aware(&mut self) {
+        // Make sure we're dealing with valid UTF-8 to begin with
+        let Ok(text) = std::str::from_utf8(&self.input) else {
+            return;
+        };
+
+        // Collect the byte offset and length of every codepoint
+        let chars: Vec<(usize, usize, char)> = text
+            .char_indices()
+            .map(|(offset, c)| (offset, c.len_utf8(), c))
+            .collect();
+
+        // Nothing to operate on in an empty input
+        if chars.is_empty() {
+            return;
+        }
+
+        // Determine the slack space we have for growing
+        let slack = self.max_size.saturating_sub(self.input.len());
+
+        // Pick a codepoint to operate on
+        let (offset, len, c) = chars[self.rand() % chars.len()];
+
+        // Determine the bytes that will replace the chosen codepoint
+        let replacement = match self.rand() % 4 {
+            // Insert a new codepoint in front of the chosen one
+            0 => {
+                let mut encoded = self.random_char().to_string();
+                encoded.push(c);
+                encoded
+            }
+            // Delete the codepoint, always leave at least one
+            1 => {
+                if chars.len() == 1 {
+                    return;
+                }
+
+                String::new()
+            }
+            // Swap the codepoint with another valid one
+            2 => self.random_char().to_string(),
+            // Flip the case of the codepoint
+            3 => {
+                if c.is_uppercase() {
+                    c.to_lowercase().collect()
+                } else {
+                    c.to_uppercase().collect()
+                }
+            }
+            _ => unreachable!(),
+        };
+
+        // Make sure we don't grow past max_size
+        if replacement.len() > len && replacement.len() - len > slack {
+            return;
+        }
+
+        // Splice the replacement in place of the chosen codepoint
+        self.input.splice(offset..offset + len, replacement.bytes());
+    }
+
+    /// Determines the window of the current input that the mutation rounds are
+    /// allowed to touch given the preserved prefix and suffix and the focus
+    /// region, None if there is nothing left that we're allowed to mutate (ie
+    /// the prefix and suffix cover the whole input)
+    fn mutable_window(&self) -> Option<Range<usize>> {
+        let mut start = self.config.preserve_prefix;
+        let mut end = self.input.len().saturating_sub(self.config.preserve_suffix);
+
+        // Clamp the window to the focus region
+        if let Some(focus) = &self.config.focus {
+            start = std::cmp::max(start, focus.start);
+            end = std::cmp::min(end, focus.end);
+        }
+
+        if start >= end {
+            return None;
+        }
+
+        Some(start..end)
+    }
+
+    /// Randomly selects a mutation strategy, longshot strategies are only
+    /// part of the candidate pool LONGSHOT_MUTATION_RATE% of the time. Returns
+    /// None if every strategy has been disabled
+    fn pick_mutation(&mut self) -> Option<MutationTypes> {
+        // Determine the pool of candidates, we don't want to frequently
+        // use longshot strategies
+        let longshot = self.rand() % 100;
+
+        // If we're within the longshot range, add them to the possible, the
+        // range shrinks with the temperature
+        let threshold = ((LONGSHOT_MUTATION_RATE + 1) as f32 * self.temperature()) as usize;
+        let mut allow_longshots = longshot < threshold;
+
+        // Count the candidates, if every strategy is a longshot we have to
+        // allow them regardless
+        let mut pool = self.candidates(allow_longshots).count();
+        if pool == 0 {
+            allow_longshots = true;
+            pool = self.candidates(allow_longshots).count();
+        }
+
+        // Nothing is selectable
+        if pool == 0 {
+            return None;
+        }
+
+        // Pick mutation type, weighted if weights were set
+        if self.weights.is_empty() {
+            let mutation_idx = self.rand() % pool;
+
+            return self.candidates(allow_longshots).nth(mutation_idx);
+        }
+
+        let total: usize = self
+            .candidates(allow_longshots)
+            .map(|m| self.weight(m))
+            .sum();
+        let mut roll = self.rand() % total;
+        for mutation in self.candidates(allow_longshots) {
+            if roll < self.weight(mutation) {
+                return Some(mutation);
+            }
+            roll -= self.weight(mutation);
+        }
+
+        unreachable!()
+    }
+
+    /// Applies a single mutation strategy to the current input and records it
+    /// in the last mutation summary
+    fn apply(&mut self, mutation: MutationTypes, corpus: &dyn CorpusView) {
+        // Match on the mutation and apply it
+        match mutation {
+            MutationTypes::ByteInsert => {
+                self.byte_insert();
+                self.last_mutation.push(MutationTypes::ByteInsert);
+            }
+            MutationTypes::ByteOverwrite => {
+                self.byte_overwrite();
+                self.last_mutation.push(MutationTypes::ByteOverwrite);
+            }
+            MutationTypes::ByteDelete => {
+                self.byte_delete();
+                self.last_mutation.push(MutationTypes::ByteDelete);
+            }
+            MutationTypes::BlockInsert => {
+                self.block_insert();
+                self.last_mutation.push(MutationTypes::BlockInsert);
+            }
+            MutationTypes::BlockOverwrite => {
+                self.block_overwrite();
+                self.last_mutation.push(MutationTypes::BlockOverwrite);
+            }
+            MutationTypes::BlockDelete => {
+                self.block_delete();
+                self.last_mutation.push(MutationTypes::BlockDelete);
+            }
+            MutationTypes::BitFlip => {
+                self.bit_flip();
+                self.last_mutation.push(MutationTypes::BitFlip);
+            }
+            MutationTypes::Grow => {
+                self.grow();
+                self.last_mutation.push(MutationTypes::Grow);
+            }
+            MutationTypes::Truncate => {
+                self.truncate();
+                self.last_mutation.push(MutationTypes::Truncate);
+            }
+            MutationTypes::MagicByteInsert => {
+                self.magic_byte_insert();
+                self.last_mutation.push(MutationTypes::MagicByteInsert);
+            }
+            MutationTypes::MagicByteOverwrite => {
+                self.magic_byte_overwrite();
+                self.last_mutation.push(MutationTypes::MagicByteOverwrite);
+            }
+            MutationTypes::Splice => {
+                self.splice(corpus, self.last_parent);
+                self.last_mutation.push(MutationTypes::Splice);
+            }
+            MutationTypes::Utf8Aware => {
+                self.utf8_aware();
+                self.last_mutation.push(MutationTypes::Utf8Aware);
+            }
+            MutationTypes::Swap => {
+                self.swap();
+                self.last_mutation.push(MutationTypes::Swap);
+            }
+            MutationTypes::RecordShuffle => {
+                self.record_shuffle();
+                self.last_mutation.push(MutationTypes::RecordShuffle);
+            }
+            MutationTypes::RecordDuplicate => {
+                self.record_duplicate();
+                self.last_mutation.push(MutationTypes::RecordDuplicate);
+            }
+            MutationTypes::RecordDelete => {
+                self.record_delete();
+                self.last_mutation.push(MutationTypes::RecordDelete);
+            }
+            MutationTypes::Reverse => {
+                self.reverse();
+                self.last_mutation.push(MutationTypes::Reverse);
+            }
+            MutationTypes::RotateBytes => {
+                self.rotate_bytes();
+                self.last_mutation.push(MutationTypes::RotateBytes);
+            }
+            MutationTypes::DictPlace => {
+                self.dict_place();
+                self.last_mutation.push(MutationTypes::DictPlace);
+            }
+            MutationTypes::Concat => {
+                self.concat(corpus);
+                self.last_mutation.push(MutationTypes::Concat);
+            }
+            MutationTypes::CopyWithin => {
+                self.copy_within();
+                self.last_mutation.push(MutationTypes::CopyWithin);
+            }
+            MutationTypes::InsertNullRun => {
+                self.insert_null_run();
+                self.last_mutation.push(MutationTypes::InsertNullRun);
+            }
+            MutationTypes::InsertHighRun => {
+                self.insert_high_run();
+                self.last_mutation.push(MutationTypes::InsertHighRun);
+            }
+            MutationTypes::FieldFlip => {
+                self.field_flip();
+                self.last_mutation.push(MutationTypes::FieldFlip);
+            }
+            MutationTypes::Scatter => {
+                self.scatter();
+                self.last_mutation.push(MutationTypes::Scatter);
+            }
+            MutationTypes::Transpose => {
+                self.transpose();
+                self.last_mutation.push(MutationTypes::Transpose);
+            }
+            MutationTypes::DeleteRange => {
+                self.delete_range();
+                self.last_mutation.push(MutationTypes::DeleteRange);
+            }
+            MutationTypes::BigramInsert => {
+                self.bigram_insert();
+                self.last_mutation.push(MutationTypes::BigramInsert);
+            }
+            MutationTypes::CaseFlip => {
+                self.case_flip();
+                self.last_mutation.push(MutationTypes::CaseFlip);
+            }
+        }
+    }
+
+    /// Picks the base for the next input, this will:
+    /// 1. Clear the current input buffer
+    /// 2. Randomly select an input from the corpus or generate one from scratch
+    ///
+    /// Returns the index of the corpus input to mutate, or None if an input was
+    /// generated from scratch into the input buffer instead
+    fn select_base(&mut self, corpus: &dyn CorpusView) -> Option<usize> {
+        // Clear current input
+        self.input.clear();
+        self.last_mutation.clear();
+        self.records.clear();
+        self.last_parent = None;
+
+        // Get the number of inputs to choose from
+        let num_inputs = corpus.num_inputs();
+
+        // n% of the time, just generate a new input from scratch
+        let gen = self.rand() % 100;
+
+        // If we don't have any inputs to choose from, create a random one, or
+        // one out of dictionary tokens if that's configured
+        if num_inputs == 0 && self.config.dictionary_scratch && !self.dictionary.is_empty() {
+            self.generate_dictionary_input();
+            return None;
+        }
+
+        let scratch_rate = self.config.gen_scratch_rate.unwrap_or(GEN_SCRATCH_RATE);
+        if num_inputs == 0 || gen < scratch_rate {
+            self.generate_random_input();
+            return None;
+        }
+
+        // Pick an input from the corpus to use
+        let mut idx = self.select_input(corpus);
+
+        // With scratch generation off, step past empty inputs to the next
+        // input we can actually mutate
+        let usable = |idx: usize| corpus.get_input(idx).is_some_and(|input| !input.is_empty());
+        if scratch_rate == 0 && !usable(idx) {
+            let mut later = (1..num_inputs).map(|offset| (idx + offset) % num_inputs);
+            if let Some(next) = later.find(|&next| usable(next)) {
+                idx = next;
+            }
+        }
+
+        Some(idx)
+    }
+
+    /// Picks the index of the corpus input to mutate according to the
+    /// configured selection mode, the corpus must not be empty. The weighted
+    /// modes weigh the whole corpus, but the weights are cached until the
+    /// corpus version changes
+    fn select_input(&mut self, corpus: &dyn CorpusView) -> usize {
+        let num_inputs = corpus.num_inputs();
+        let greedy = match self.config.selection {
+            Selection::Uniform => return self.rand() % num_inputs,
+            Selection::Priority { greedy } => greedy,
+            _ => false,
+        };
+
+        // Greedy always picks the heaviest input, the first one on a tie
+        if greedy {
+            let weights = &self.selection_weights(corpus).weights;
+            let mut best = 0;
+            for (idx, &weight) in weights.iter().enumerate() {
+                if weight > weights[best] {
+                    best = idx;
+                }
+            }
+            return best;
+        }
+
+        // Nothing has any weight, fall back to a uniform pick
+        let draw = self.rand();
+        let cache = self.selection_weights(corpus);
+        if cache.total <= 0.0 || !cache.total.is_finite() {
+            return draw % num_inputs;
+        }
+
+        // Roll against the total weight and find the input it lands on
+        let mut roll = (draw as f64 / usize::MAX as f64) * cache.total;
+        for (idx, &weight) in cache.weights.iter().enumerate() {
+            if roll < weight {
+                return idx;
+            }
+            roll -= weight;
+        }
+
+        // Rounding can leave the roll just past the end
+        cache
+            .weights
+            .iter()
+            .rposition(|&weight| weight > 0.0)
+            .unwrap_or(num_inputs - 1)
+    }
+
+    /// Retrieves the selection weight of every input in the corpus for the
+    /// configured selection mode, recomputing them only if the corpus, its
+    /// version, or the mode changed since they were cached. Inputs are
+    /// weighed inversely proportional to their length, proportional to their
+    /// cached coverage, or by their priority. Priorities that were never set
+    /// count as `DEFAULT_PRIORITY`
+    fn selection_weights(&mut self, corpus: &dyn CorpusView) -> &SelectionCache {
+        let addr = corpus as *const dyn CorpusView as *const () as usize;
+        let key = corpus
+            .version()
+            .map(|version| (addr, version, self.config.selection));
+
+        if key.is_none()
+            || key != self.selection.key
+            || self.selection.weights.len() != corpus.num_inputs()
+        {
+            let weights: Vec<f64> = (0..corpus.num_inputs())
+                .map(|idx| match self.config.selection {
+                    Selection::SmallestBiased => {
+                        let len = corpus.get_input(idx).map_or(0, |input| input.len());
+                        1.0 / len.max(1) as f64
+                    }
+                    // Inputs without cached coverage still get a chance
+                    Selection::CoverageWeighted => {
+                        (corpus.coverage_size(idx).unwrap_or(0) + 1) as f64
+                    }
+                    Selection::Priority { .. } => {
+                        corpus.priority(idx).unwrap_or(DEFAULT_PRIORITY).max(0.0)
+                    }
+                    Selection::Uniform => 1.0,
+                })
+                .collect();
+
+            self.selection = SelectionCache {
+                key,
+                total: weights.iter().sum(),
+                weights,
+            };
+        }
+
+        &self.selection
+    }
+
+    /// Applies mutation rounds to the part of the input buffer we're allowed
+    /// to mutate, up to `max_rounds` of them. If `forced` is set, exactly one
+    /// round of that strategy is applied instead of a random stack
+    fn mutate_window(
+        &mut self,
+        corpus: &dyn CorpusView,
+        forced: Option<MutationTypes>,
+        max_rounds: usize,
+    ) {
+        self.with_window(|mutator, head, tail| {
+            // We have an input, pick a number of rounds of mutation
+            let rounds = match forced {
+                Some(_) => 1,
+                None => (mutator.rand() % max_rounds) + 1,
+            };
+
+            // A forced strategy is exactly one round, otherwise we may have to
+            // keep going until enough rounds actually changed the input
+            let required = match forced {
+                Some(_) => 0,
+                None => mutator.config.require_effective_rounds,
+            };
+            let max_applied = rounds + required * EFFECTIVE_ROUND_RETRIES;
+            let mut applied = 0;
+            let mut effective = 0;
+
+            // Apply mutations for number of rounds
+            while applied < rounds || (effective < required && applied < max_applied) {
+                let Some(mutation) = forced.or_else(|| mutator.pick_mutation()) else {
+                    break;
+                };
+                mutator.records.push(MutationRecord {
+                    mutation,
+                    rng: mutator.rng,
+                });
+
+                // Only pay for the copy if we're counting effective rounds
+                let before = (required > 0).then(|| mutator.input.clone());
+                if mutator.config.profile {
+                    let start = Instant::now();
+                    mutator.apply(mutation, corpus);
+                    mutator.stats.record_timing(mutation, start.elapsed());
+                } else {
+                    mutator.apply(mutation, corpus);
+                }
+                applied += 1;
+                if before.is_some_and(|before| before != mutator.input) {
+                    effective += 1;
+                }
+
+                // Show the observer the whole buffer, not just the window
+                if let Some(observer) = &mutator.observer {
+                    let state = [head, &mutator.input, tail].concat();
+                    // A panicking observer poisons the lock, it's still usable
+                    let mut observer = observer.lock().unwrap_or_else(PoisonError::into_inner);
+                    observer(&mutation, &state);
+                }
+            }
+        });
+    }
+
+    /// Runs `rounds` over just the mutable window of the input, it's handed
+    /// the head and tail of the input that are set aside while it runs
+    fn with_window(&mut self, rounds: impl FnOnce(&mut Self, &[u8], &[u8])) {
+        // A base that's already over max_size (ie max_size was lowered after
+        // the corpus was loaded) is cut down before we mutate it
+        if self.input.len() > self.max_size {
+            if !self.warned_over_cap {
+                prompt_warn!(
+                    "Corpus input of {} bytes is over the {} byte max, truncating",
+                    self.input.len(),
+                    self.max_size
+                );
+                self.warned_over_cap = true;
+            }
+
+            self.truncate_input(self.max_size);
+        }
+
+        // Set aside everything outside of the window we're allowed to mutate
+        // so that none of the mutation rounds can touch it
+        let Some(window) = self.mutable_window() else {
+            return;
+        };
+
+        let tail = self.input.split_off(window.end);
+        let head: Vec<u8> = self.input.drain(..window.start).collect();
+
+        // Inside of a focus region we can't grow, otherwise we can grow as
+        // long as the whole input stays within max_size
+        let max_size = self.max_size;
+        self.set_max_size(if self.config.focus.is_some() {
+            window.len()
+        } else {
+            max_size.saturating_sub(head.len() + tail.len())
+        });
+
+        // Strategies that work with absolute offsets into the input translate
+        // them by where the window starts
+        self.window_start = window.start;
+        self.window_tail = tail.len();
+        rounds(self, &head, &tail);
+        self.window_start = 0;
+        self.window_tail = 0;
+
+        // Put the untouched bytes back around the mutated window
+        self.set_max_size(max_size);
+        self.input.splice(0..0, head);
+        self.input.extend_from_slice(&tail);
+
+        // This isn't prod, but a broken invariant shouldn't take down the
+        // whole process in a release build either
+        debug_assert!(!self.input.is_empty());
+        debug_assert!(self.input.len() <= self.max_size);
+        self.repair_input();
+    }
+
+    /// Cuts the input down to `len` bytes, the excess is taken out from just
+    /// in front of the preserved suffix so a trailing footer survives
+    fn truncate_input(&mut self, len: usize) {
+        let excess = self.input.len().saturating_sub(len);
+        if excess == 0 {
+            return;
+        }
+
+        let end = self.input.len() - std::cmp::min(self.config.preserve_suffix, len);
+        self.input.drain(end - excess..end);
+    }
+
+    /// Fixes up an input that ended up empty or larger than max_size, an
+    /// empty input is replaced with a fresh one and an oversized one is cut
+    /// down to max_size
+    fn repair_input(&mut self) {
+        if self.input.is_empty() {
+            prompt_warn!("Mutator produced an empty input, generating a new one");
+            self.generate_random_input();
+        } else if self.input.len() > self.max_size {
+            prompt_warn!(
+                "Mutator produced a {} byte input over the {} byte max, truncating",
+                self.input.len(),
+                self.max_size
+            );
+            self.truncate_input(self.max_size);
+        }
+    }
+
+    /// Pads the input out to `len` bytes of random data, the padding goes in
+    /// front of the preserved suffix so a trailing footer stays trailing
+    fn pad_input(&mut self, len: usize) {
+        let at = self.input.len().saturating_sub(self.config.preserve_suffix);
+        let padding: Vec<u8> = (self.input.len()..len)
+            .map(|_| core_mutations::remap_byte(self.rand() as u8, &self.alphabet))
+            .collect();
+        self.input.splice(at..at, padding);
+    }
+
+    /// Runs the post-processor over the finished input and then makes sure it
+    /// satisfies the invariant, if either is set
+    fn finish_input(&mut self) {
+        if self.input.len() < self.config.min_size {
+            self.pad_input(self.config.min_size);
+        }
+
+        if let Some(post_process) = &self.post_process {
+            let mut post_process = post_process.lock().unwrap_or_else(PoisonError::into_inner);
+            post_process(&mut self.input);
+        }
+
+        if !cfg!(debug_assertions) && !self.config.check_invariants {
+            return;
+        }
+
+        if let Some(invariant) = &self.invariant {
+            assert!(
+                invariant(&self.input),
+                "Mutator produced an input that violates the invariant: {:02X?}",
+                &self.input[..std::cmp::min(self.input.len(), 16)]
+            );
+        }
+    }
+
+    /// Picks an input from the corpus and applies exactly one mutation of the
+    /// given strategy to it, there is no stacking and no longshot roll. The
+    /// strategy is applied even if it wouldn't currently be selected, which
+    /// makes bisecting a crash by mutation strategy straightforward
+    pub fn mutate_single(&mut self, corpus: &dyn CorpusView, ty: MutationTypes) {
+        // Clear current input
+        self.input.clear();
+        self.last_mutation.clear();
+        self.records.clear();
+        self.last_parent = None;
+
+        // Start from a random corpus input, or a random one if there are none
+        let num_inputs = corpus.num_inputs();
+        if num_inputs > 0 {
+            let idx = self.select_input(corpus);
+            self.input.extend_from_slice(corpus.get_input(idx).unwrap());
+            self.last_parent = Some(idx);
+        }
+
+        if self.input.is_empty() {
+            self.last_parent = None;
+            self.generate_random_input();
+        }
+
+        self.mutate_window(corpus, Some(ty), 1);
+        self.record_history();
+    }
+
+    /// Mutates the corpus input at `idx` rather than a random pick, there's no
+    /// roll to generate from scratch. `intensity` scales the upper bound on
+    /// the number of stacked mutation rounds so a scheduler can spend more
+    /// effort on promising inputs, ie 2.0 allows up to 2 * MAX_STACK rounds
+    /// and 1.0 is what `mutate_input` uses for the input it picks
+    pub fn mutate_from(&mut self, corpus: &dyn CorpusView, idx: usize, intensity: f32) {
+        // Clear current input
+        self.input.clear();
+        self.last_mutation.clear();
+        self.records.clear();
+        self.last_parent = None;
+
+        // Start from the requested input, or a random one if there isn't one
+        if let Some(chosen) = corpus.get_input(idx) {
+            self.input.extend_from_slice(chosen);
+            self.last_parent = Some(idx);
+        }
+
+        if self.input.is_empty() {
+            self.last_parent = None;
+            self.generate_random_input();
+        } else {
+            let max_rounds = self.scaled_rounds(MAX_STACK as f32 * intensity);
+
+            self.mutate_window(corpus, None, max_rounds);
+        }
+
+        self.record_history();
+        self.finish_input();
+    }
+
+    /// Walks the corpus in order, each call loads the next input (truncated to
+    /// max_size) into the input buffer and returns it, with a single mutation
+    /// applied if `walk_mutate` is set. Once every input has been handed out
+    /// the walk returns None and starts over on the next call, so a full pass
+    /// is exactly `num_inputs` outputs followed by a None
+    pub fn corpus_walk(&mut self, corpus: &dyn CorpusView) -> Option<&[u8]> {
+        // End of the pass, the next call starts from the beginning
+        if self.walk_cursor >= corpus.num_inputs() {
+            self.walk_cursor = 0;
+            return None;
+        }
+
+        let idx = self.walk_cursor;
+        self.walk_cursor += 1;
+
+        self.last_mutation.clear();
+        self.records.clear();
+        self.memcpy_input(corpus.get_input(idx).unwrap());
+        self.last_parent = Some(idx);
+
+        if self.config.walk_mutate && !self.input.is_empty() {
+            self.mutate_window(corpus, None, 1);
+            self.record_history();
+        }
+
+        Some(&self.input)
+    }
+
+    /// Mutates the corpus input at `idx` like `mutate_from` but with the RNG
+    /// seeded from the input's content hash and `variant`, so a sweep over a
+    /// corpus produces the same outputs no matter what order inputs are
+    /// visited in or what the Mutator did before. Splicing still draws donors
+    /// from `corpus`, so outputs only repeat against the same corpus. Our own
+    /// RNG stream is left where it was
+    #[allow(dead_code)]
+    pub fn mutate_deterministic_for(
+        &mut self,
+        corpus: &dyn CorpusView,
+        idx: usize,
+        variant: usize,
+    ) -> Vec<u8> {
+        let rng = self.rng;
+        let hash = corpus.content_hash(idx).unwrap_or(0) as usize;
+        self.rng = sanitize_state(hash ^ variant);
+
+        self.mutate_from(corpus, idx, 1.0);
+
+        self.rng = rng;
+        self.input.clone()
+    }
+
+    /// Runs an AFL-style per-seed schedule over `base`: the deterministic
+    /// stages first, each exactly once, then havoc. The deterministic stages
+    /// are, in order:
+    /// 1. A walk flipping every single bit, lowest bit of each byte first
+    /// 2. A walk writing every interesting 8-bit value at every byte
+    /// 3. A walk adding and subtracting 1 through ARITH_MAX at every byte
+    ///
+    /// Walk outputs that would be identical to `base` are skipped. Havoc then
+    /// yields `MutatorConfig::schedule_havoc` stacked mutations of `base`
+    /// (SCHEDULE_HAVOC by default). Outputs are produced lazily
+    #[allow(dead_code)]
+    pub fn schedule<'a>(
+        &'a mut self,
+        base: &'a [u8],
+        corpus: &'a dyn CorpusView,
+    ) -> impl Iterator<Item = Vec<u8>> + 'a {
+        let with_byte = move |idx: usize, byte: u8| -> Option<Vec<u8>> {
+            if base[idx] == byte {
+                return None;
+            }
+
+            let mut out = base.to_vec();
+            out[idx] = byte;
+            Some(out)
+        };
+
+        // Deterministic stages
+        let bit_walk = (0..base.len() * 8).map(move |bit| {
+            let mut out = base.to_vec();
+            out[bit / 8] ^= 1 << (bit % 8);
+            out
+        });
+
+        let interesting_walk = (0..base.len()).flat_map(move |idx| {
+            INTERESTING_8
+                .iter()
+                .filter_map(move |&byte| with_byte(idx, byte))
+        });
+
+        let arith_walk = (0..base.len()).flat_map(move |idx| {
+            (1..=ARITH_MAX).flat_map(move |delta| {
+                [base[idx].wrapping_add(delta), base[idx].wrapping_sub(delta)]
+                    .into_iter()
+                    .filter_map(move |byte| with_byte(idx, byte))
+            })
+        });
+
+        // Havoc
+        let havoc_iters = self.config.schedule_havoc.unwrap_or(SCHEDULE_HAVOC);
+        let havoc = (0..havoc_iters).map(move |_| {
+            self.havoc_from(corpus, base);
+            self.input.clone()
+        });
+
+        bit_walk
+            .chain(interesting_walk)
+            .chain(arith_walk)
+            .chain(havoc)
+    }
+
+    /// Applies a random stack of mutations to a copy of `base` that didn't
+    /// come from the corpus, an empty base is generated from scratch instead
+    fn havoc_from(&mut self, corpus: &dyn CorpusView, base: &[u8]) {
+        self.input.clear();
+        self.last_mutation.clear();
+        self.records.clear();
+        self.last_parent = None;
+
+        let len = std::cmp::min(base.len(), self.max_size);
+        self.input.extend_from_slice(&base[..len]);
+
+        if self.input.is_empty() {
+            self.generate_random_input();
+        } else {
+            let max_rounds = self.scaled_rounds(MAX_STACK as f32);
+            self.mutate_window(corpus, None, max_rounds);
+        }
+
+        self.record_history();
+    }
+
+    /// Describes how the current input differs from `base`, ie the input it
+    /// was mutated from, for logging and visualization. This only trims the
+    /// common prefix and suffix, so whatever changed in between is reported
+    /// as a single edit rather than a minimal diff. Identical inputs have no
+    /// edits
+    #[allow(dead_code)]
+    pub fn diff_from_base(&self, base: &[u8]) -> Vec<EditOp> {
+        let input = &self.input;
+
+        // Find how much the two share at either end
+        let prefix = base.iter().zip(input).take_while(|(a, b)| a == b).count();
+        let max_suffix = std::cmp::min(base.len(), input.len()) - prefix;
+        let suffix = base
+            .iter()
+            .rev()
+            .zip(input.iter().rev())
+            .take(max_suffix)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        let removed = prefix..base.len() - suffix;
+        let added = &input[prefix..input.len() - suffix];
+
+        match (removed.is_empty(), added.is_empty()) {
+            (true, true) => Vec::new(),
+            (true, false) => vec![EditOp::Insert(prefix, added.to_vec())],
+            (false, true) => vec![EditOp::Delete(removed)],
+            (false, false) => vec![EditOp::Replace(removed, added.to_vec())],
+        }
+    }
+
+    /// Recombines two parents at a single crossover point shared by both,
+    /// returning the complementary offspring (head of `a` with the tail of
+    /// `b`, and head of `b` with the tail of `a`). The point is strictly inside
+    /// both parents when they're long enough to allow it, and offspring past
+    /// max_size are truncated
+    #[allow(dead_code)]
+    pub fn crossover_pair(&mut self, a: &[u8], b: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        // Pick the crossover point, avoiding the ends so the offspring aren't
+        // just copies of the parents
+        let shortest = std::cmp::min(a.len(), b.len());
+        let point = if shortest >= 2 {
+            (self.rand() % (shortest - 1)) + 1
+        } else {
+            shortest
+        };
+
+        let mut first = [&a[..point], &b[point..]].concat();
+        let mut second = [&b[..point], &a[point..]].concat();
+        first.truncate(self.max_size);
+        second.truncate(self.max_size);
+
+        (first, second)
+    }
+
+    /// Builds the inputs within `radius` edits of `input` to probe whether a
+    /// flaky crash is sensitive to small changes. A single edit is a flip of
+    /// one bit, dropping the last byte, or appending a zero byte (if there's
+    /// room before max_size), so radius 1 yields `8 * len + 2` neighbors. No
+    /// randomness is involved, the set and its order only depend on `input`.
+    /// The input itself is never included and there are at most
+    /// `MAX_NEIGHBORS` results
+    #[allow(dead_code)]
+    pub fn neighborhood(&mut self, input: &[u8], radius: usize) -> Vec<Vec<u8>> {
+        let mut seen: HashSet<Vec<u8>> = HashSet::new();
+        seen.insert(input.to_vec());
+
+        let mut neighbors = Vec::new();
+        let mut frontier = vec![input.to_vec()];
+
+        // Expand one edit at a time, breadth first
+        for _ in 0..radius {
+            let mut next = Vec::new();
+
+            for base in frontier.iter() {
+                let mut edits = Vec::new();
+
+                // Every single bit flip
+                for offset in 0..base.len() {
+                    for bit in 0..8 {
+                        let mut flipped = base.clone();
+                        flipped[offset] ^= 1 << bit;
+                        edits.push(flipped);
+                    }
+                }
+
+                // One byte shorter and one byte longer
+                if !base.is_empty() {
+                    edits.push(base[..base.len() - 1].to_vec());
+                }
+                if base.len() < self.max_size {
+                    let mut longer = base.clone();
+                    longer.push(0);
+                    edits.push(longer);
+                }
+
+                for edit in edits {
+                    if neighbors.len() >= MAX_NEIGHBORS {
+                        return neighbors;
+                    }
+
+                    if seen.insert(edit.clone()) {
+                        next.push(edit.clone());
+                        neighbors.push(edit);
+                    }
+                }
+            }
+
+            frontier = next;
+        }
+
+        neighbors
+    }
+
+    /// Computes a cheap structural fingerprint of an input for bucketing a
+    /// corpus without coverage, structurally similar inputs share one. It
+    /// combines the power of two length bucket, the share of printable,
+    /// control, and high bytes rounded to quarters, the first byte, and the
+    /// byte class at a few evenly spaced positions
+    #[allow(dead_code)]
+    pub fn shape_signature(input: &[u8]) -> u64 {
+        // Printable text (including whitespace) is 0, control is 1, high is 2
+        let class = |byte: u8| -> usize {
+            match byte {
+                b'\t' | b'\n' | b'\r' | 0x20..=0x7E => 0,
+                0x80..=0xFF => 2,
+                _ => 1,
+            }
+        };
+
+        let len_bucket = usize::BITS - input.len().leading_zeros();
+
+        let mut histogram = [0usize; 3];
+        for &byte in input {
+            histogram[class(byte)] += 1;
+        }
+        let quarters = histogram.map(|count| count * 4 / input.len().max(1));
+
+        let samples: Vec<usize> = (0..5)
+            .filter_map(|i| input.get(i * input.len().saturating_sub(1) / 4))
+            .map(|&byte| class(byte))
+            .collect();
+
+        let mut hasher = DefaultHasher::new();
+        (len_bucket, quarters, input.first(), samples).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Scores an input by how many distinct `NOVELTY_NGRAM_LEN` byte n-grams
+    /// it has that aren't in `seen_ngrams`, so a batch can be ordered before
+    /// it's run. This is a cheap heuristic, not a stand-in for real coverage
+    #[allow(dead_code)]
+    pub fn novelty_score(&self, input: &[u8], seen_ngrams: &HashSet<u64>) -> u32 {
+        let unseen: HashSet<u64> = input
+            .windows(NOVELTY_NGRAM_LEN)
+            .map(ngram_hash)
+            .filter(|gram| !seen_ngrams.contains(gram))
+            .collect();
+
+        unseen.len() as u32
+    }
+
+    /// Retrieves the replayable mutation rounds that produced the current input
+    /// from its parent, see `reproduce`
+    pub fn last_records(&self) -> &[MutationRecord] {
+        &self.records
+    }
+
+    /// Rebuilds an input by finding the corpus input whose content hash is
+    /// `base_hash` and replaying `records` on top of it. The Mutator needs the
+    /// same max_size and configuration it had when the records were taken,
+    /// including the post-processor, since the input is finished the same way
+    /// too. The Mutator's own RNG state is left untouched. Returns None if no
+    /// corpus input has that hash
+    #[allow(dead_code)]
+    pub fn reproduce(
+        &mut self,
+        corpus: &dyn CorpusView,
+        base_hash: u64,
+        records: &[MutationRecord],
+    ) -> Option<Vec<u8>> {
+        let idx =
+            (0..corpus.num_inputs()).find(|&idx| corpus.content_hash(idx) == Some(base_hash))?;
+
+        // Start from the base input
+        self.input.clear();
+        self.last_mutation.clear();
+        self.records.clear();
+        self.input.extend_from_slice(corpus.get_input(idx)?);
+        self.last_parent = Some(idx);
+
+        // Replay each round from its recorded RNG state
+        let rng = self.rng;
+        self.with_window(|mutator, _, _| {
+            for record in records {
+                mutator.rng = record.rng;
+                mutator.records.push(*record);
+                mutator.apply(record.mutation, corpus);
+            }
+        });
+
+        // Nothing draws between the last round and the finish, so padding up
+        // to min_size picks up from where the last round left the RNG
+        self.finish_input();
+        self.rng = rng;
+
+        Some(self.input.clone())
+    }
+
+    /// Retrieves the index of the corpus input that the current input was
+    /// mutated from, None if it was generated from scratch or copied in
+    pub fn last_parent(&self) -> Option<usize> {
+        self.last_parent
+    }
+
+    /// Retrieves the Mutator's own counters
+    #[allow(dead_code)]
+    pub fn stats(&self) -> &MutatorStats {
+        &self.stats
+    }
+
+    /// Retrieves the session history of per-call mutation sequences, oldest
+    /// first. This is only recorded while `accumulate_history` is set
+    pub fn history(&self) -> &VecDeque<Vec<MutationTypes>> {
+        &self.history
+    }
+
+    /// Appends the latest mutation sequence to the session history if history
+    /// is being accumulated, the oldest sequence is dropped past MAX_HISTORY
+    fn record_history(&mut self) {
+        if !self.config.accumulate_history {
+            return;
+        }
+
+        if self.history.len() == MAX_HISTORY {
+            self.history.pop_front();
+        }
+
+        self.history.push_back(self.last_mutation.clone());
+    }
+
+    /// Mutates a new input into the Mutator's own input buffer
+    pub fn mutate_input(&mut self, corpus: &dyn CorpusView) {
+        let mut out = std::mem::take(&mut self.input);
+        self.mutate_into(corpus, &mut out);
+        self.input = out;
+    }
+
+    /// Mutates a new input like `mutate_input` but catches any panic from
+    /// inside the mutator and reports it as an error rather than unwinding,
+    /// this is meant for fuzzing the mutator itself with odd corpora. On error
+    /// the input buffer is left empty
+    #[allow(dead_code)]
+    pub fn try_mutate(&mut self, corpus: &dyn CorpusView) -> Result<&[u8], LucidErr> {
+        // A panic mid-round can leave max_size shrunk to the mutable window
+        let max_size = self.max_size;
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            self.mutate_input(corpus);
+        }));
+
+        if let Err(payload) = result {
+            self.set_max_size(max_size);
+            self.input.clear();
+
+            // Panic payloads are almost always a &str or a String
+            let reason = payload
+                .downcast_ref::<&str>()
+                .map(|reason| reason.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+
+            return Err(LucidErr::Mutator(format!("panicked: {}", reason)));
+        }
+
+        Ok(&self.input)
+    }
+
+    /// Runs `iters` mutations and tallies how many times each strategy was
+    /// selected, keyed by strategy name, to check empirically that masks and
+    /// longshot rates do what they're supposed to. Inputs generated from
+    /// scratch don't select any strategy
+    #[allow(dead_code)]
+    pub fn audit_distribution(
+        &mut self,
+        corpus: &dyn CorpusView,
+        iters: usize,
+    ) -> HashMap<String, usize> {
+        let mut histogram = HashMap::new();
+
+        for _ in 0..iters {
+            self.mutate_input(corpus);
+            for mutation in self.last_mutation.iter() {
+                *histogram.entry(format!("{:?}", mutation)).or_insert(0) += 1;
+            }
+        }
+
+        histogram
+    }
+
+    /// Applies every mutation strategy once through `mutate_single` to shake
+    /// out panics and warm caches before a timed campaign. Every strategy is
+    /// tried even if an earlier one fails, the error lists all the strategies
+    /// that panicked. The input buffer holds the last strategy's output
+    #[allow(dead_code)]
+    pub fn warmup(&mut self, corpus: &dyn CorpusView) -> Result<(), LucidErr> {
+        let max_size = self.max_size;
+        let mut failed = Vec::new();
+
+        for &ty in MutationTypes::all() {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                self.mutate_single(corpus, ty);
+            }));
+
+            if result.is_err() {
+                self.set_max_size(max_size);
+                self.input.clear();
+                failed.push(format!("{:?}", ty));
+            }
+        }
+
+        if !failed.is_empty() {
+            return Err(LucidErr::Mutator(format!(
+                "warmup failed for: {}",
+                failed.join(", ")
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Mutates a new input into a caller-owned buffer, `out` is cleared first
+    /// and its capacity is reused so a consumer that owns its buffer doesn't
+    /// need to clone our input buffer every iteration. Our own input buffer is
+    /// left as it was
+    pub fn mutate_into(&mut self, corpus: &dyn CorpusView, out: &mut Vec<u8>) {
+        // Borrow the caller's buffer as our input buffer for the duration
+        std::mem::swap(&mut self.input, out);
+        match self.select_base(corpus) {
+            Some(idx) => self.mutate_from(corpus, idx, 1.0),
+            None => {
+                self.record_history();
+                self.finish_input();
+            }
+        }
+        std::mem::swap(&mut self.input, out);
+    }
+
+    /// Dry-runs `mutate_input` and returns the sequence of mutation strategies
+    /// it would apply given the current RNG state. The work is done on a
+    /// throwaway copy of the Mutator so neither our input buffer nor our RNG
+    /// state changes, a following `mutate_input` applies the same sequence.
+    /// The observer isn't notified about a dry-run and the post-processor and
+    /// invariant check don't run
+    #[allow(dead_code)]
+    pub fn plan(&self, corpus: &dyn CorpusView) -> Vec<MutationTypes> {
+        let mut shadow = self.clone();
+        shadow.observer = None;
+        shadow.post_process = None;
+        shadow.invariant = None;
+        shadow.mutate_input(corpus);
+
+        shadow.last_mutation
+    }
+
+    /// Clears the current mutator input buffer and copies a passed in slice
+    /// into the input buffer. Slices longer than max_size (ie some Redqueen
+    /// generated inputs) are truncated to max_size so the input buffer never
+    /// violates the size invariant the mutation strategies rely on
+    pub fn memcpy_input(&mut self, slice: &[u8]) {
+        // Clear the current input, it doesn't descend from the corpus anymore
+        self.input.clear();
+        self.last_parent = None;
+
+        // Copy the passed in buffer, up to max_size
+        let len = std::cmp::min(slice.len(), self.max_size);
+        self.input.extend_from_slice(&slice[..len]);
+    }
+}
+
+/// Drives the mutator over stdin/stdout so that harnesses written in other
+/// languages can use it, see `run_io` for the protocol
+#[cfg(feature = "stdio")]
+#[allow(dead_code)]
+pub fn run_stdio(mutator: &mut Mutator, corpus: &dyn CorpusView) -> Result<(), LucidErr> {
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    run_io(mutator, corpus, &mut stdin.lock(), &mut stdout.lock())
+}
+
+/// Reads one count per line from `reader` and answers each count by writing
+/// that many mutated inputs to `writer`, each prefixed by its length as a
+/// little-endian u32. Stops at EOF or an empty line
+#[allow(dead_code)]
+pub fn run_io(
+    mutator: &mut Mutator,
+    corpus: &dyn CorpusView,
+    reader: &mut dyn std::io::BufRead,
+    writer: &mut dyn std::io::Write,
+) -> Result<(), LucidErr> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line).map_err(LucidErr::Io)?;
+
+        // EOF or an empty line means we're done
+        let request = line.trim();
+        if request.is_empty() {
+            return Ok(());
+        }
+
+        let Ok(count) = request.parse::<usize>() else {
+            return Err(LucidErr::Mutator(format!("Invalid count '{}'", request)));
+        };
+
+        // Emit the mutations
+        for _ in 0..count {
+            mutator.mutate_input(corpus);
+
+            let len = mutator.input.len() as u32;
+            writer
+                .write_all(&len.to_le_bytes())
+                .and_then(|_| writer.write_all(&mutator.input))
+                .map_err(LucidErr::Io)?;
+        }
+
+        // The harness is probably waiting on this batch
+        writer.flush().map_err(LucidErr::Io)?;
+    }
+}
+
+/// Saves the mutation rounds that produced an input, along with the content
+/// hash of the corpus input they were applied to, so the input can be rebuilt
+/// later with `load_record` and `Mutator::reproduce`. The format is the magic
+/// "LREC", a u32 version, the u64 base hash, a u32 record count, and then a
+/// u8 strategy and u64 RNG state per record, all little-endian
+pub fn save_record(
+    path: &std::path::Path,
+    base_hash: u64,
+    records: &[MutationRecord],
+) -> Result<(), LucidErr> {
+    let Ok(count) = u32::try_from(records.len()) else {
+        return Err(LucidErr::Mutator(
+            "Too many mutation records to save".to_string(),
+        ));
+    };
+
+    let mut data = Vec::with_capacity(20 + records.len() * 9);
+    data.extend_from_slice(RECORD_MAGIC);
+    data.extend_from_slice(&RECORD_VERSION.to_le_bytes());
+    data.extend_from_slice(&base_hash.to_le_bytes());
+    data.extend_from_slice(&count.to_le_bytes());
+    for record in records {
+        data.push(record.mutation.as_u8());
+        data.extend_from_slice(&(record.rng as u64).to_le_bytes());
+    }
+
+    std::fs::write(path, data).map_err(|e| {
+        LucidErr::Mutator(format!(
+            "Unable to save mutation record '{}', error: {}",
+            path.display(),
+            e
+        ))
+    })
+}
+
+/// Loads a file written by `save_record`, returns the base hash and the
+/// mutation records
+#[allow(dead_code)]
+pub fn load_record(path: &std::path::Path) -> Result<(u64, Vec<MutationRecord>), LucidErr> {
+    let data = std::fs::read(path).map_err(|e| {
+        LucidErr::Mutator(format!(
+            "Unable to read mutation record '{}', error: {}",
+            path.display(),
+            e
+        ))
+    })?;
+
+    // Check the header
+    if data.len() < 20 || &data[..4] != RECORD_MAGIC {
+        return Err(LucidErr::Mutator("Not a mutation record file".to_string()));
+    }
+
+    let version = u32::from_le_bytes(data[4..8].try_into().unwrap());
+    if version != RECORD_VERSION {
+        return Err(LucidErr::Mutator(format!(
+            "Unsupported mutation record version {}",
+            version
+        )));
+    }
+
+    let base_hash = u64::from_le_bytes(data[8..16].try_into().unwrap());
+    let count = u32::from_le_bytes(data[16..20].try_into().unwrap()) as usize;
+
+    // Parse the records
+    let body = &data[20..];
+    if body.len() != count * 9 {
+        return Err(LucidErr::Mutator(
+            "Truncated mutation record file".to_string(),
+        ));
+    }
+
+    let mut records = Vec::with_capacity(count);
+    for chunk in body.chunks_exact(9) {
+        let Ok(mutation) = MutationTypes::try_from(chunk[0]) else {
+            return Err(LucidErr::Mutator(format!(
+                "Unknown mutation strategy {} in record",
+                chunk[0]
+            )));
+        };
+        let rng = u64::from_le_bytes(chunk[1..].try_into().unwrap()) as usize;
+        records.push(MutationRecord { mutation, rng });
+    }
+
+    Ok((base_hash, records))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::Corpus;
+
+    #[test]
+    fn observer_is_shared_between_clones() {
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        let seen = Arc::new(Mutex::new(0));
+        let counter = seen.clone();
+        mutator.set_observer(Box::new(move |_, _| *counter.lock().unwrap() += 1));
+
+        let corpus = Corpus::from_inputs(vec![b"lucid".to_vec()]);
+        let mut clone = mutator.clone();
+        mutator.mutate_single(&corpus, MutationTypes::BitFlip);
+        clone.mutate_single(&corpus, MutationTypes::BitFlip);
+        assert_eq!(*seen.lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn builder_builds_a_configured_mutator() {
+        let magic = [0x4141_4141_4141_4141];
+        let mut mutator = Mutator::builder()
+            .seed(0x1337)
+            .max_size(64)
+            .focus(0..16)
+            .config(MutatorConfig {
+                profile: true,
+                ..MutatorConfig::default()
+            })
+            .min_size(32)
+            .weights(&[
+                (MutationTypes::MagicByteOverwrite, 1),
+                (MutationTypes::Swap, 0),
+            ])
+            .disable(MutationTypes::BitFlip)
+            .disable(MutationTypes::Transpose)
+            .enable(MutationTypes::Transpose)
+            .longshots(&[MutationTypes::MagicByteOverwrite])
+            .temperature(0.5)
+            .magic_numbers(&magic)
+            .dictionary(vec![DictToken {
+                bytes: b"lucid".to_vec(),
+                preferred_offset: None,
+            }])
+            .taboo_bytes(&[0xFF])
+            .build()
+            .unwrap();
+
+        // The focus survives the later config and the rest is applied on top
+        assert_eq!(mutator.config.focus, Some(0..16));
+        assert!(mutator.config.profile);
+        assert_eq!(mutator.config.min_size, 32);
+        assert_eq!(mutator.magic_numbers(), magic);
+        assert_eq!(mutator.dictionary.len(), 1);
+        assert!(!mutator.is_selectable(MutationTypes::Swap));
+        assert!(!mutator.is_selectable(MutationTypes::BitFlip));
+        assert!(mutator.is_selectable(MutationTypes::Transpose));
+        assert_eq!(mutator.temperature(), 0.5);
+
+        let corpus = Corpus::from_inputs(vec![vec![0; 24]]);
+        for _ in 0..1000 {
+            mutator.mutate_input(&corpus);
+            if mutator.last_parent().is_none() {
+                continue;
+            }
+
+            // Mutations stay in the focus and the input is padded to min_size
+            assert_eq!(mutator.input.len(), 32);
+            assert!(mutator.input[16..24].iter().all(|&byte| byte == 0));
+            assert!(mutator.input[24..].iter().all(|&byte| byte != 0xFF));
+            assert!(mutator
+                .last_mutation
+                .iter()
+                .all(|&ty| ty != MutationTypes::Swap && ty != MutationTypes::BitFlip));
+        }
+    }
+
+    #[test]
+    fn builder_rejects_nonsense() {
+        let builder = Mutator::builder().max_size(64);
+        assert!(builder.clone().min_size(65).build().is_err());
+        assert!(builder.clone().magic_numbers(&[]).build().is_err());
+        assert!(builder
+            .clone()
+            .focus(32..128)
+            .config(MutatorConfig::default())
+            .build()
+            .is_err());
+
+        let weights: Vec<_> = MUTATIONS.iter().map(|&ty| (ty, 0)).collect();
+        assert!(builder.clone().weights(&weights).build().is_err());
+        assert!(builder.min_size(64).build().is_ok());
+    }
+
+    #[test]
+    fn panicking_hook_is_an_error() {
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        mutator.set_post_processor(Box::new(|_| panic!("bad hook")));
+
+        let corpus = Corpus::from_inputs(vec![b"lucid".to_vec()]);
+        match mutator.try_mutate(&corpus) {
+            Err(LucidErr::Mutator(reason)) => assert!(reason.contains("bad hook")),
+            _ => panic!("panicking hook wasn't reported"),
+        }
+        assert!(mutator.input.is_empty());
+        assert_eq!(mutator.max_size, 64);
+
+        // The mutator is still usable once the hook is replaced
+        mutator.set_post_processor(Box::new(|_| ()));
+        assert!(mutator.try_mutate(&corpus).is_ok());
+    }
+
+    #[test]
+    fn mutator_with_hooks_is_send() {
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        mutator.set_observer(Box::new(|_, _| ()));
+        mutator.set_post_processor(Box::new(|input| input[0] = b'L'));
+        mutator.set_invariant_check(Box::new(|input| input[0] == b'L'));
+
+        // The hooks still run once the Mutator is moved to another thread
+        let mutator = std::thread::spawn(move || {
+            let corpus = Corpus::from_inputs(vec![b"lucid".to_vec()]);
+            mutator.mutate_input(&corpus);
+            mutator
+        })
+        .join()
+        .unwrap();
+        assert_eq!(mutator.input[0], b'L');
+    }
+
+    #[test]
+    fn utf8_aware_keeps_input_valid() {
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        for _ in 0..1000 {
+            mutator.input = "héllo wörld".as_bytes().to_vec();
+            mutator.utf8_aware();
+            assert!(std::str::from_utf8(&mutator.input).is_ok());
+            assert!(mutator.input.len() <= 64);
+        }
+    }
+
+    #[test]
+    fn utf8_aware_empty_input_is_nop() {
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        mutator.input.clear();
+        mutator.utf8_aware();
+        assert!(mutator.input.is_empty());
+    }
+
+    #[test]
+    fn over_cap_base_keeps_suffix() {
+        let corpus = Corpus::from_inputs(vec![b"HDR:some body bytes here;CRC!".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 16);
+        mutator.config.preserve_suffix = 4;
+
+        for _ in 0..1000 {
+            mutator.mutate_input(&corpus);
+            if mutator.last_parent().is_some() {
+                assert!(mutator.input.len() <= 16);
+                assert!(mutator.input.ends_with(b"CRC!"));
+            }
+        }
+    }
+
+    #[test]
+    fn dict_place_preferred_offset_is_absolute() {
+        let corpus = Corpus::from_inputs(vec![b"HDR:............".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 16);
+        mutator.config.preserve_prefix = 4;
+        mutator.load_dictionary_positional(vec![DictToken {
+            bytes: b"TOK".to_vec(),
+            preferred_offset: Some(8),
+        }]);
+
+        let mut placed = 0;
+        for _ in 0..1000 {
+            mutator.mutate_single(&corpus, MutationTypes::DictPlace);
+            assert!(mutator.input.starts_with(b"HDR:"));
+            if &mutator.input[8..11] == b"TOK" {
+                placed += 1;
+            }
+        }
+        assert!(placed > 800);
+    }
+
+    #[test]
+    fn truncate_snaps_to_absolute_boundaries() {
+        let corpus = Corpus::from_inputs(vec![b"HDR:aaaabbbbcccc".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 16);
+        mutator.config.preserve_prefix = 4;
+        mutator.config.record_boundaries = vec![6, 10];
+
+        for _ in 0..1000 {
+            mutator.mutate_single(&corpus, MutationTypes::Truncate);
+            assert!([6, 10, 16].contains(&mutator.input.len()));
+        }
+    }
+
+    #[test]
+    fn field_flip_offsets_are_absolute() {
+        let corpus = Corpus::from_inputs(vec![b"HDR:\x00\x00abcdefghCRC!".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 32);
+        mutator.config.preserve_prefix = 4;
+        mutator.config.preserve_suffix = 4;
+        mutator.declare_field(4, 2, FieldKind::Length);
+        mutator.declare_field(6, 1, FieldKind::Signed);
+        mutator.declare_field(0, 4, FieldKind::Unsigned);
+
+        let mut saw_length = false;
+        for _ in 0..1000 {
+            mutator.mutate_single(&corpus, MutationTypes::FieldFlip);
+            assert_eq!(mutator.input.len(), 18);
+            assert!(mutator.input.starts_with(b"HDR:"));
+            assert!(mutator.input.ends_with(b"CRC!"));
+            assert_eq!(&mutator.input[7..14], b"bcdefgh");
+
+            // The length field counts everything after it, footer included
+            saw_length |= mutator.input[4..6] == [12, 0];
+        }
+        assert!(saw_length);
+    }
+
+    #[test]
+    fn smallest_biased_handles_huge_inputs() {
+        let huge = vec![0x41; 2 << 20];
+        let corpus = Corpus::from_inputs(vec![huge.clone(), [huge.clone(), vec![0x42]].concat()]);
+        let mut mutator = Mutator::new(Some(0x1337), 4 << 20);
+        mutator.config.selection = Selection::SmallestBiased;
+
+        for _ in 0..100 {
+            assert!(mutator.select_input(&corpus) < 2);
+        }
+    }
+
+    #[test]
+    fn selection_weights_follow_corpus_version() {
+        let mut corpus = Corpus::from_inputs(vec![b"a".to_vec(), b"b".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        mutator.config.selection = Selection::Priority { greedy: true };
+
+        assert_eq!(mutator.select_input(&corpus), 0);
+
+        // Changing a priority invalidates the cached weights
+        corpus.set_priority(1, 5.0);
+        assert_eq!(mutator.select_input(&corpus), 1);
+
+        // And so does adding an input
+        corpus.add_input(b"c".to_vec());
+        corpus.set_priority(2, 10.0);
+        assert_eq!(mutator.select_input(&corpus), 2);
+    }
+
+    #[test]
+    fn truncate_input_takes_bytes_before_suffix() {
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        mutator.config.preserve_suffix = 4;
+        mutator.input = b"0123456789CRC!".to_vec();
+        mutator.truncate_input(8);
+        assert_eq!(mutator.input, b"0123CRC!");
+    }
+
+    #[test]
+    fn longshot_set_can_be_overridden() {
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        assert!(mutator.is_longshot(MutationTypes::Splice));
+        assert!(!mutator
+            .candidates(false)
+            .any(|ty| ty == MutationTypes::Splice));
+
+        // Splice moves into the common pool, and ByteInsert is demoted
+        mutator.set_longshot(&[MutationTypes::ByteInsert]);
+        assert!(!mutator.is_longshot(MutationTypes::Splice));
+        assert!(mutator
+            .candidates(false)
+            .any(|ty| ty == MutationTypes::Splice));
+        assert!(!mutator
+            .candidates(false)
+            .any(|ty| ty == MutationTypes::ByteInsert));
+    }
+
+    #[test]
+    fn stable_donors_survive_reordering() {
+        let inputs = vec![
+            b"first donor".to_vec(),
+            b"second donor".to_vec(),
+            b"third donor".to_vec(),
+            b"fourth donor".to_vec(),
+        ];
+        let mut reordered = inputs.clone();
+        reordered.reverse();
+        let corpus = Corpus::from_inputs(inputs);
+        let reordered = Corpus::from_inputs(reordered);
+
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        mutator.config.stable_splice_donors = true;
+        let mut replay = mutator.clone();
+
+        for _ in 0..100 {
+            mutator.input = b"recipient".to_vec();
+            replay.input = b"recipient".to_vec();
+            mutator.splice(&corpus, None);
+            replay.splice(&reordered, None);
+            assert_eq!(mutator.input, replay.input);
+        }
+    }
+
+    #[test]
+    fn lowering_max_size_below_input_doesnt_panic() {
+        let corpus = Corpus::from_inputs(vec![vec![0x41; 256], b"lucid".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 256);
+        mutator.mutate_input(&corpus);
+
+        // The loaded input is now well over the cap
+        mutator.input = vec![0x41; 256];
+        mutator.set_max_size(8);
+        for _ in 0..1000 {
+            mutator.mutate_input(&corpus);
+            assert!(mutator.input.len() <= 8);
+        }
+    }
+
+    #[test]
+    fn preserved_prefix_is_never_modified() {
+        let corpus = Corpus::from_inputs(vec![
+            b"HEADER!!some body bytes".to_vec(),
+            b"HEADER!!donor bytes for splicing".to_vec(),
+            b"HEADER!!".to_vec(),
+        ]);
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        mutator.config.preserve_prefix = 8;
+
+        for _ in 0..10000 {
+            mutator.mutate_input(&corpus);
+            if mutator.last_parent().is_some() {
+                assert!(mutator.input.starts_with(b"HEADER!!"));
+            }
+        }
+    }
+
+    #[test]
+    fn rng_state_replays_the_stream() {
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        mutator.rand();
+
+        let state = mutator.rng_state();
+        let first: Vec<usize> = (0..8).map(|_| mutator.rand()).collect();
+        mutator.set_rng_state(state);
+        let second: Vec<usize> = (0..8).map(|_| mutator.rand()).collect();
+        assert_eq!(first, second);
+
+        // A zero state would leave xorshift stuck
+        mutator.set_rng_state(0);
+        assert_ne!(mutator.rng_state(), 0);
+        assert_ne!(mutator.rand(), 0);
+    }
+
+    #[test]
+    fn plan_matches_the_next_mutation() {
+        let corpus = Corpus::from_inputs(vec![b"hello world".to_vec(), b"AAAABBBB".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+
+        for _ in 0..100 {
+            let planned = mutator.plan(&corpus);
+            mutator.mutate_input(&corpus);
+            assert_eq!(planned, mutator.last_mutation);
+        }
+    }
+
+    #[test]
+    fn record_ops_move_whole_lines() {
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        mutator.set_record_delimiter(b'\n');
+
+        let mut shuffled = false;
+        for _ in 0..100 {
+            mutator.memcpy_input(b"a\nb\nc");
+            mutator.record_shuffle();
+
+            let mut lines: Vec<&[u8]> = mutator.input.split(|&b| b == b'\n').collect();
+            shuffled |= lines != [b"a", b"b", b"c"];
+            lines.sort();
+            assert_eq!(lines, [b"a", b"b", b"c"]);
+        }
+        assert!(shuffled);
+
+        for _ in 0..100 {
+            mutator.memcpy_input(b"a\nb\nc");
+            mutator.record_duplicate();
+
+            let lines: Vec<&[u8]> = mutator.input.split(|&b| b == b'\n').collect();
+            assert_eq!(lines.len(), 4);
+            assert!(lines.windows(2).any(|pair| pair[0] == pair[1]));
+            assert!(lines.iter().all(|line| line.len() == 1));
+        }
+
+        mutator.memcpy_input(b"a\nb\nc");
+        mutator.record_delete();
+        assert_eq!(mutator.input.split(|&b| b == b'\n').count(), 2);
+    }
+
+    #[test]
+    fn magic_width_fixes_fragment_size() {
+        let mut mutator = Mutator::new(Some(0x1337), 4096);
+        mutator.config.magic_width = Some(4);
+
+        for _ in 0..1000 {
+            assert_eq!(mutator.mutate_magic(0x4142434445464748).len(), 4);
+        }
+
+        for _ in 0..1000 {
+            mutator.memcpy_input(b"some input bytes");
+            mutator.magic_byte_insert();
+            assert_eq!((mutator.input.len() - 16) % 4, 0);
+        }
+    }
+
+    #[test]
+    fn focus_confines_mutations() {
+        let corpus = Corpus::from_inputs(vec![b"0123456789ABCDEF".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        mutator.set_focus(Some(4..8));
+
+        for _ in 0..10000 {
+            mutator.mutate_input(&corpus);
+            if mutator.last_parent().is_none() {
+                continue;
+            }
+
+            assert_eq!(mutator.input.len(), 16);
+            assert_eq!(&mutator.input[..4], b"0123");
+            assert_eq!(&mutator.input[8..], b"89ABCDEF");
+        }
+
+        // Clearing the focus lets mutations reach the whole buffer again
+        mutator.set_focus(None);
+        let touched = (0..1000).any(|_| {
+            mutator.mutate_input(&corpus);
+            mutator.input.len() != 16 || mutator.input[..4] != *b"0123"
+        });
+        assert!(touched);
+    }
+
+    #[test]
+    fn mutate_into_matches_mutate_input() {
+        let corpus = Corpus::from_inputs(vec![b"hello world".to_vec(), b"AAAABBBB".to_vec()]);
+        let mut a = Mutator::new(Some(0x1337), 64);
+        let mut b = Mutator::new(Some(0x1337), 64);
+
+        let mut out = Vec::with_capacity(64);
+        let capacity = out.capacity();
+        for _ in 0..1000 {
+            a.mutate_input(&corpus);
+            b.mutate_into(&corpus, &mut out);
+            assert_eq!(a.input, out);
+        }
+
+        // Nothing outgrew max_size so the buffer was never reallocated
+        assert_eq!(out.capacity(), capacity);
+        assert!(b.input.is_empty());
+    }
+
+    #[test]
+    fn memcpy_input_truncates_to_max_size() {
+        let mut mutator = Mutator::new(Some(0x1337), 16);
+        mutator.memcpy_input(&[0x41; 100]);
+        assert_eq!(mutator.input, [0x41; 16]);
+
+        // Growing from a truncated input stays within the cap
+        let corpus = Corpus::from_inputs(vec![vec![0x42; 8]]);
+        mutator.memcpy_input(&[0x41; 100]);
+        mutator.mutate_input(&corpus);
+        assert!(mutator.input.len() <= 16);
+    }
+
+    #[test]
+    fn large_splice_bias_grows_grafts() {
+        let corpus = Corpus::from_inputs(vec![vec![0x42; 64]]);
+
+        let average_graft = |bias: bool| {
+            let mut mutator = Mutator::new(Some(0x1337), 256);
+            mutator.config.large_splice_bias = bias;
+
+            let mut total = 0;
+            for _ in 0..10000 {
+                mutator.memcpy_input(&[0x41; 8]);
+                mutator.splice(&corpus, None);
+                total += mutator.input.iter().filter(|&&b| b == 0x42).count();
+            }
+
+            total as f64 / 10000.0
+        };
+
+        let plain = average_graft(false);
+        let biased = average_graft(true);
+        assert!(biased > plain * 1.2, "{} vs {}", biased, plain);
+    }
+
+    #[test]
+    fn builtin_magic_numbers_are_exported() {
+        assert!(!builtin_magic_numbers().is_empty());
+        assert!(builtin_magic_numbers().contains(&(u32::MAX as u64)));
+    }
+
+    #[test]
+    fn splice_keeps_recipient_head_with_oversized_donor() {
+        let corpus = Corpus::from_inputs(vec![vec![0x42; 1000]]);
+        let recipient: Vec<u8> = (0..32).collect();
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        mutator.config.splice_keep_recipient_head = true;
+
+        for _ in 0..1000 {
+            mutator.memcpy_input(&recipient);
+            mutator.splice(&corpus, None);
+            assert!(mutator.input.len() <= 64);
+
+            // A prefix of the recipient followed by a block of the donor
+            let head = mutator.input.iter().position(|&b| b == 0x42).unwrap();
+            assert!(head > 0);
+            assert_eq!(mutator.input[..head], recipient[..head]);
+            assert!(mutator.input[head..].iter().all(|&b| b == 0x42));
+        }
+    }
+
+    #[test]
+    fn observer_sees_every_round() {
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let steps = seen.clone();
+        mutator.set_observer(Box::new(move |&mutation, _| {
+            steps.lock().unwrap().push(mutation)
+        }));
+
+        let corpus = Corpus::from_inputs(vec![b"hello world".to_vec()]);
+        for _ in 0..100 {
+            mutator.mutate_input(&corpus);
+            let steps = std::mem::take(&mut *seen.lock().unwrap());
+            assert_eq!(steps, mutator.last_mutation);
+        }
+    }
+
+    #[test]
+    fn mutate_single_applies_exactly_one_mutation() {
+        let corpus = Corpus::from_inputs(vec![b"hello world".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+
+        for _ in 0..100 {
+            mutator.mutate_single(&corpus, MutationTypes::BitFlip);
+            assert_eq!(mutator.last_mutation, [MutationTypes::BitFlip]);
+            assert_eq!(mutator.input.len(), 11);
+        }
+    }
+
+    #[test]
+    fn random_input_fills_a_draw_at_a_time() {
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        let mut replay = Mutator::new(Some(0x1337), 64);
+
+        for _ in 0..100 {
+            mutator.generate_random_input();
+
+            // Each draw supplies 8 little-endian bytes, the last one is cut
+            let size = (replay.rand() % 64) + 1;
+            let mut expected = Vec::new();
+            while expected.len() < size {
+                expected.extend_from_slice(&replay.rand().to_le_bytes());
+            }
+            expected.truncate(size);
+            assert_eq!(mutator.input, expected);
+        }
+
+        // Multi-megabyte inputs only cost a draw per 8 bytes
+        let mut mutator = Mutator::new(Some(0x1337), 16 * 1024 * 1024);
+        mutator.generate_random_input();
+        assert!(!mutator.input.is_empty() && mutator.input.len() <= 16 * 1024 * 1024);
+    }
+
+    #[test]
+    fn concat_appends_whole_donor() {
+        let corpus = Corpus::from_inputs(vec![b"BODY".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 16);
+
+        mutator.memcpy_input(b"HEAD");
+        mutator.concat(&corpus);
+        assert_eq!(mutator.input, b"HEADBODY");
+
+        // The donor is cut to the slack and a full input is left alone
+        mutator.memcpy_input(b"HEADERHEADER");
+        mutator.concat(&corpus);
+        assert_eq!(mutator.input, b"HEADERHEADERBODY");
+        mutator.concat(&corpus);
+        assert_eq!(mutator.input.len(), 16);
+
+        mutator.memcpy_input(b"HEADERHEADERHEA");
+        mutator.concat(&corpus);
+        assert_eq!(mutator.input, b"HEADERHEADERHEAB");
+    }
+
+    #[test]
+    fn intensity_scales_mutation_rounds() {
+        let corpus = Corpus::from_inputs(vec![vec![0x41; 32]]);
+
+        let average_rounds = |intensity: f32| {
+            let mut mutator = Mutator::new(Some(0x1337), 64);
+            let mut total = 0;
+            for _ in 0..10000 {
+                mutator.mutate_from(&corpus, 0, intensity);
+                total += mutator.last_mutation.len();
+            }
+
+            total as f64 / 10000.0
+        };
+
+        let ratio = average_rounds(2.0) / average_rounds(1.0);
+        assert!((1.7..2.3).contains(&ratio), "{}", ratio);
+    }
+
+    #[test]
+    fn empty_corpus_builds_inputs_from_dictionary() {
+        let corpus = Corpus::from_inputs(vec![]);
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        mutator.config.dictionary_scratch = true;
+        mutator.load_dictionary_positional(vec![
+            DictToken {
+                bytes: b"GET ".to_vec(),
+                preferred_offset: None,
+            },
+            DictToken {
+                bytes: b"POST".to_vec(),
+                preferred_offset: None,
+            },
+        ]);
+
+        for _ in 0..1000 {
+            mutator.mutate_input(&corpus);
+
+            // Every 4 byte chunk is a token, the last one may be cut short
+            for chunk in mutator.input.chunks(4) {
+                assert!(b"GET ".starts_with(chunk) || b"POST".starts_with(chunk));
+            }
+        }
+    }
+
+    #[test]
+    fn disabled_strategies_are_never_selected() {
+        let corpus = Corpus::from_inputs(vec![b"hello world".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        for &ty in MutationTypes::all() {
+            mutator.disable(ty);
+        }
+        mutator.enable(MutationTypes::BitFlip);
+
+        for _ in 0..1000 {
+            mutator.mutate_input(&corpus);
+            assert!(mutator
+                .last_mutation
+                .iter()
+                .all(|&ty| ty == MutationTypes::BitFlip));
+        }
+    }
+
+    #[test]
+    fn ensemble_is_reproducible() {
+        let corpus = Corpus::from_inputs(vec![b"hello world".to_vec()]);
+        let seeds = [1, 2, 3];
+
+        let first = Mutator::ensemble(&seeds, 64, &corpus, 4);
+        let second = Mutator::ensemble(&seeds, 64, &corpus, 4);
+        assert_eq!(first.len(), 12);
+        assert_eq!(first, second);
+
+        // Each seed's stream is its own
+        assert_ne!(
+            first[..4],
+            Mutator::ensemble(&[4, 5, 6], 64, &corpus, 4)[..4]
+        );
+    }
+
+    #[test]
+    fn run_io_answers_counts_with_prefixed_mutations() {
+        let corpus = Corpus::from_inputs(vec![b"hello world".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        let mut replay = Mutator::new(Some(0x1337), 64);
+
+        let mut reader = std::io::Cursor::new(b"2\n1\n\n".to_vec());
+        let mut output = Vec::new();
+        run_io(&mut mutator, &corpus, &mut reader, &mut output).unwrap();
+
+        let mut expected = Vec::new();
+        for _ in 0..3 {
+            replay.mutate_input(&corpus);
+            expected.extend_from_slice(&(replay.input.len() as u32).to_le_bytes());
+            expected.extend_from_slice(&replay.input);
+        }
+        assert_eq!(output, expected);
+
+        let mut reader = std::io::Cursor::new(b"lots\n".to_vec());
+        assert!(run_io(&mut mutator, &corpus, &mut reader, &mut output).is_err());
+    }
+
+    // run_stdio needs a real stdin, so it's run in a copy of the test binary
+    #[cfg(feature = "stdio")]
+    #[test]
+    fn run_stdio_serves_stdin() {
+        let corpus = Corpus::from_inputs(vec![b"hello world".to_vec()]);
+        if std::env::var_os("LUCID_STDIO_CHILD").is_some() {
+            let mut mutator = Mutator::new(Some(0x1337), 64);
+            run_stdio(&mut mutator, &corpus).unwrap();
+            return;
+        }
+
+        let mut child = std::process::Command::new(std::env::current_exe().unwrap())
+            .args([
+                "--exact",
+                "mutator::tests::run_stdio_serves_stdin",
+                "--nocapture",
+            ])
+            .env("LUCID_STDIO_CHILD", "1")
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        std::io::Write::write_all(&mut child.stdin.take().unwrap(), b"1\n").unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success());
+
+        let mut replay = Mutator::new(Some(0x1337), 64);
+        replay.mutate_input(&corpus);
+        let mut expected = (replay.input.len() as u32).to_le_bytes().to_vec();
+        expected.extend_from_slice(&replay.input);
+        assert!(output.stdout.windows(expected.len()).any(|w| w == expected));
+    }
+
+    #[test]
+    fn history_accumulates_per_call() {
+        let corpus = Corpus::from_inputs(vec![b"hello world".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        mutator.mutate_input(&corpus);
+        assert!(mutator.history().is_empty());
+
+        mutator.config.accumulate_history = true;
+        for _ in 0..3 {
+            mutator.mutate_input(&corpus);
+            assert_eq!(mutator.history().back(), Some(&mutator.last_mutation));
+        }
+        assert_eq!(mutator.history().len(), 3);
+
+        // The history is capped
+        for _ in 0..MAX_HISTORY {
+            mutator.mutate_input(&corpus);
+        }
+        assert_eq!(mutator.history().len(), MAX_HISTORY);
+    }
+
+    #[test]
+    fn zero_temperature_is_one_round_without_longshots() {
+        let corpus = Corpus::from_inputs(vec![b"hello world".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        mutator.set_temperature(0.0);
+
+        for _ in 0..10000 {
+            mutator.mutate_input(&corpus);
+            if mutator.last_parent().is_none() {
+                continue;
+            }
+
+            assert_eq!(mutator.last_mutation.len(), 1);
+            assert!(!mutator.is_longshot(mutator.last_mutation[0]));
+        }
+
+        // Out of range temperatures are clamped
+        mutator.set_temperature(7.0);
+        assert_eq!(mutator.temperature(), 1.0);
+    }
+
+    #[test]
+    fn magic_values_are_emitted_in_both_byte_orders() {
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        mutator.config.magic_width = Some(2);
+
+        let outputs: Vec<Vec<u8>> = (0..1000).map(|_| mutator.mutate_magic(0x0100)).collect();
+        assert!(outputs.contains(&vec![0x00, 0x01]));
+        assert!(outputs.contains(&vec![0x01, 0x00]));
+    }
+
+    #[test]
+    fn length_field_gets_boundary_values() {
+        let corpus = Corpus::from_inputs(vec![b"\x08\x00abcdefgh".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 32);
+        mutator.declare_field(0, 2, FieldKind::Length);
+
+        // 0, 1, max, actual -/+ 1 and the remaining length -/+ 0 or 1
+        let expected = [0, 1, 0xFFFF, 7, 9, 8];
+        let mut seen = std::collections::HashSet::new();
+        for _ in 0..1000 {
+            mutator.mutate_single(&corpus, MutationTypes::FieldFlip);
+            let value = u16::from_le_bytes([mutator.input[0], mutator.input[1]]);
+            assert!(expected.contains(&value), "{}", value);
+            assert_eq!(&mutator.input[2..], b"abcdefgh");
+            seen.insert(value);
+        }
+        assert_eq!(seen.len(), expected.len());
+    }
+
+    #[test]
+    fn reseed_deterministic_is_reproducible() {
+        let mut a = Mutator::new(Some(0x1337), 64);
+        let mut b = Mutator::new(Some(0x1337), 64);
+
+        let mut seeds = std::collections::HashSet::new();
+        for _ in 0..10 {
+            let seed = a.reseed_deterministic();
+            assert_eq!(seed, b.reseed_deterministic());
+            assert_eq!(a.rand(), b.rand());
+            seeds.insert(seed);
+        }
+        assert_eq!(seeds.len(), 10);
+
+        // It's a different stream than the one we were on
+        let mut c = Mutator::new(Some(0x1337), 64);
+        c.rand();
+        let mut d = Mutator::new(Some(0x1337), 64);
+        d.rand();
+        d.reseed_deterministic();
+        assert_ne!(c.rand(), d.rand());
+    }
+
+    #[test]
+    fn mutation_types_round_trip_through_u8() {
+        assert_eq!(MutationTypes::all().len(), MutationTypes::count());
+
+        for (idx, &ty) in MutationTypes::all().iter().enumerate() {
+            assert_eq!(ty.as_u8() as usize, idx);
+            assert_eq!(MutationTypes::try_from(ty.as_u8()), Ok(ty));
+        }
+
+        assert!(MutationTypes::try_from(MutationTypes::count() as u8).is_err());
+    }
+
+    #[test]
+    fn taboo_bytes_are_never_emitted() {
+        let mut mutator = Mutator::new(Some(0x1337), 256);
+        mutator.set_taboo_bytes(b"\r\n");
+
+        for _ in 0..1000 {
+            mutator.generate_random_input();
+            mutator.byte_insert();
+            mutator.byte_overwrite();
+            mutator.grow();
+            assert!(!mutator.input.iter().any(|&b| b == b'\r' || b == b'\n'));
+        }
+
+        // Clearing the set allows everything again
+        mutator.set_taboo_bytes(&[]);
+        let emitted = (0..1000).any(|_| {
+            mutator.generate_random_input();
+            mutator.input.contains(&b'\n')
+        });
+        assert!(emitted);
+    }
+
+    #[test]
+    fn record_round_trips_and_reproduces() {
+        let corpus = Corpus::from_inputs(vec![b"hello world".to_vec(), b"AAAABBBB".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        let path = std::env::temp_dir().join(format!("lucid_record_{}", std::process::id()));
+
+        for _ in 0..100 {
+            mutator.mutate_input(&corpus);
+            let Some(parent) = mutator.last_parent() else {
+                continue;
+            };
+
+            let base_hash = corpus.content_hash(parent).unwrap();
+            save_record(&path, base_hash, mutator.last_records()).unwrap();
+            let (loaded_hash, records) = load_record(&path).unwrap();
+            assert_eq!(loaded_hash, base_hash);
+            assert_eq!(records.len(), mutator.last_records().len());
+
+            let input = mutator.input.clone();
+            let mut replay = Mutator::new(Some(0xDEAD), 64);
+            assert_eq!(
+                replay.reproduce(&corpus, loaded_hash, &records),
+                Some(input)
+            );
+        }
+
+        // Inputs padded up to min_size are rebuilt with the same padding
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        mutator.config.min_size = 48;
+        let mut rebuilt = 0;
+        for _ in 0..100 {
+            mutator.mutate_input(&corpus);
+            let Some(parent) = mutator.last_parent() else {
+                continue;
+            };
+
+            let base_hash = corpus.content_hash(parent).unwrap();
+            save_record(&path, base_hash, mutator.last_records()).unwrap();
+            let (loaded_hash, records) = load_record(&path).unwrap();
+
+            let mut replay = Mutator::new(Some(0xDEAD), 64);
+            replay.config.min_size = 48;
+            assert_eq!(
+                replay.reproduce(&corpus, loaded_hash, &records),
+                Some(mutator.input.clone())
+            );
+            rebuilt += 1;
+        }
+        assert!(rebuilt > 0);
+
+        // Garbage isn't a record
+        std::fs::write(&path, b"nope").unwrap();
+        assert!(load_record(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn fill_randoms_matches_rand() {
+        let mut batched = Mutator::new(Some(0x1337), 64);
+        let mut single = Mutator::new(Some(0x1337), 64);
+
+        let mut out = [0usize; 64];
+        batched.fill_randoms(&mut out);
+        let expected: Vec<usize> = (0..64).map(|_| single.rand()).collect();
+        assert_eq!(out[..], expected[..]);
+
+        // Both streams carry on from the same place
+        assert_eq!(batched.rand(), single.rand());
+    }
+
+    #[test]
+    fn advance_for_child_hands_out_distinct_seeds() {
+        let mut a = Mutator::new(Some(0x1337), 64);
+        let mut b = Mutator::new(Some(0x1337), 64);
+
+        let seeds: Vec<usize> = (0..100).map(|_| a.advance_for_child()).collect();
+        let replayed: Vec<usize> = (0..100).map(|_| b.advance_for_child()).collect();
+        assert_eq!(seeds, replayed);
+
+        let distinct: std::collections::HashSet<usize> = seeds.iter().copied().collect();
+        assert_eq!(distinct.len(), 100);
+        assert!(!seeds.contains(&0));
+    }
+
+    #[test]
+    fn smallest_biased_prefers_small_inputs() {
+        let corpus = Corpus::from_inputs(vec![vec![0x41; 64], vec![0x42; 4], vec![0x43; 16]]);
+        let mut mutator = Mutator::new(Some(0x1337), 128);
+        mutator.config.selection = Selection::SmallestBiased;
+
+        let mut picks = [0; 3];
+        for _ in 0..10000 {
+            picks[mutator.select_input(&corpus)] += 1;
+        }
+
+        assert!(picks[1] > picks[2] && picks[2] > picks[0], "{:?}", picks);
+    }
+
+    #[test]
+    fn broken_invariant_is_repaired() {
+        let mut mutator = Mutator::new(Some(1), 16);
+
+        // An empty input is swapped for a fresh one
+        mutator.input.clear();
+        mutator.repair_input();
+        assert!(!mutator.input.is_empty());
+        assert!(mutator.input.len() <= 16);
+
+        // An oversized input is cut down to max_size
+        mutator.input = vec![0x41; 64];
+        mutator.repair_input();
+        assert_eq!(mutator.input, vec![0x41; 16]);
+    }
+
+    #[test]
+    fn novel_ngrams_score_higher() {
+        let mutator = Mutator::new(Some(1), 64);
+        let seen: HashSet<u64> = b"AAAAAAAAAAAAAAAA"
+            .windows(NOVELTY_NGRAM_LEN)
+            .map(ngram_hash)
+            .collect();
+
+        let stale = mutator.novelty_score(b"AAAAAAAAAAAA", &seen);
+        let novel = mutator.novelty_score(b"the quick brown fox", &seen);
+        assert_eq!(stale, 0);
+        assert!(novel > stale);
+    }
+
+    #[test]
+    fn target_size_spreads_output_sizes() {
+        let corpus = Corpus::from_inputs(vec![vec![0x41; 8]]);
+
+        // Finds the largest share of outputs in any one 32 byte wide size
+        // bucket, the more evenly spread the sizes the smaller it is
+        let most_crowded = |target_size| {
+            let mut mutator = Mutator::new(Some(0x1337), 256);
+            mutator.config.target_size = target_size;
+
+            let mut buckets = [0; 8];
+            for i in 0..2000 {
+                let ty = if i % 2 == 0 {
+                    MutationTypes::Grow
+                } else {
+                    MutationTypes::Truncate
+                };
+                mutator.mutate_single(&corpus, ty);
+                buckets[(mutator.input.len() - 1) / 32] += 1;
+            }
+
+            buckets.into_iter().max().unwrap()
+        };
+
+        let steered = most_crowded(Some(SizeDist::Uniform { min: 1, max: 256 }));
+        let unsteered = most_crowded(None);
+        assert!(steered < 2000 / 4, "{}", steered);
+        assert!(steered < unsteered, "{} vs {}", steered, unsteered);
+    }
+
+    #[test]
+    fn target_size_leaves_an_empty_window_alone() {
+        let mut mutator = Mutator::new(Some(0x1337), 256);
+        mutator.config.target_size = Some(SizeDist::Uniform { min: 1, max: 256 });
+
+        mutator.input.clear();
+        mutator.grow();
+        mutator.truncate();
+        assert!(mutator.input.is_empty());
+    }
+
+    #[test]
+    fn single_byte_overwrite_diffs_as_one_replace() {
+        let mut mutator = Mutator::new(Some(1), 64);
+        let base = b"hello world".to_vec();
+
+        mutator.input = base.clone();
+        assert!(mutator.diff_from_base(&base).is_empty());
+
+        mutator.input[6] = b'W';
+        assert_eq!(
+            mutator.diff_from_base(&base),
+            vec![EditOp::Replace(6..7, b"W".to_vec())]
+        );
+    }
+
+    #[test]
+    fn magic_width_weights_pick_fragment_size() {
+        let mut mutator = Mutator::new(Some(0x1337), 4096);
+        mutator.config.magic_width_weights = Some([0, 0, 1, 0]);
+
+        for _ in 0..1000 {
+            assert_eq!(mutator.mutate_magic(0x4142434445464748).len(), 4);
+        }
+    }
+
+    #[test]
+    fn schedule_starts_with_single_bit_flips() {
+        let corpus = Corpus::from_inputs(vec![b"AB".to_vec()]);
+        let mut mutator = Mutator::new(Some(1), 64);
+        mutator.config.schedule_havoc = Some(4);
+
+        let outputs: Vec<Vec<u8>> = mutator.schedule(b"AB", &corpus).collect();
+        for (bit, output) in outputs[..16].iter().enumerate() {
+            let mut expected = b"AB".to_vec();
+            expected[bit / 8] ^= 1 << (bit % 8);
+            assert_eq!(output, &expected);
+        }
+
+        // Neither byte is an interesting value so no walk output is skipped,
+        // and the havoc outputs come last
+        let walks = 16 + 2 * INTERESTING_8.len() + 2 * 2 * ARITH_MAX as usize;
+        assert_eq!(outputs.len(), walks + 4);
+    }
+
+    #[test]
+    fn deletes_on_tiny_inputs_are_nops() {
+        let mut mutator = Mutator::new(Some(1), 64);
+
+        mutator.input.clear();
+        mutator.byte_delete();
+        mutator.block_delete();
+        assert!(mutator.input.is_empty());
+
+        mutator.input = vec![0x41];
+        mutator.byte_delete();
+        mutator.block_delete();
+        assert_eq!(mutator.input, [0x41]);
+    }
+
+    #[test]
+    fn deterministic_sweep_ignores_selection_order() {
+        let corpus = Corpus::from_inputs(vec![b"first seed".to_vec(), b"second seed".to_vec()]);
+        let mut a = Mutator::new(Some(1), 64);
+        let mut b = Mutator::new(Some(2), 64);
+
+        // Visit the inputs in opposite orders with unrelated RNG states
+        let forward: Vec<Vec<u8>> = (0..2)
+            .map(|idx| a.mutate_deterministic_for(&corpus, idx, 7))
+            .collect();
+        let backward: Vec<Vec<u8>> = (0..2)
+            .rev()
+            .map(|idx| b.mutate_deterministic_for(&corpus, idx, 7))
+            .collect();
+
+        assert_eq!(forward[0], backward[1]);
+        assert_eq!(forward[1], backward[0]);
+        assert_ne!(
+            a.mutate_deterministic_for(&corpus, 0, 7),
+            a.mutate_deterministic_for(&corpus, 0, 8)
+        );
+    }
+
+    #[test]
+    fn tag_aware_splice_keeps_to_the_same_tag() {
+        let mut corpus = Corpus::from_inputs((0..8u8).map(|i| vec![i; 8]).collect());
+        for idx in 0..8 {
+            corpus.set_tag(idx, if idx % 2 == 0 { "png" } else { "json" });
+        }
+
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        mutator.last_parent = Some(2);
+        assert_eq!(mutator.tagged_donor(&corpus), None);
+
+        mutator.config.tag_aware_splice = true;
+        for _ in 0..1000 {
+            let donor = mutator.tagged_donor(&corpus).unwrap();
+            assert_eq!(corpus.tag(donor), Some("png"));
+        }
+    }
+
+    #[test]
+    fn warmup_succeeds_on_a_reasonable_corpus() {
+        let corpus = Corpus::from_inputs(vec![
+            b"GET /index.html HTTP/1.1\r\n\r\n".to_vec(),
+            b"{\"key\": [1, 2, 3]}".to_vec(),
+            vec![0x41],
+        ]);
+        let mut mutator = Mutator::new(Some(0x1337), 256);
+
+        assert!(mutator.warmup(&corpus).is_ok());
+        assert!(!mutator.input.is_empty());
+    }
+
+    #[test]
+    fn audit_shows_only_weighted_strategies() {
+        let corpus = Corpus::from_inputs(vec![b"some seed input".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        let mut weights: Vec<_> = MUTATIONS.iter().map(|&ty| (ty, 0)).collect();
+        weights.push((MutationTypes::BitFlip, 3));
+        weights.push((MutationTypes::ByteOverwrite, 1));
+        mutator.set_weights(&weights);
+
+        let histogram = mutator.audit_distribution(&corpus, 10000);
+        let mut names: Vec<&str> = histogram.keys().map(|name| name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["BitFlip", "ByteOverwrite"]);
+
+        let ratio = histogram["BitFlip"] as f64 / histogram["ByteOverwrite"] as f64;
+        assert!((2.5..3.5).contains(&ratio), "{}", ratio);
+    }
+
+    #[test]
+    fn over_cap_corpus_inputs_are_truncated() {
+        let corpus = Corpus::from_inputs(vec![b"ABCDEFGH".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 4);
+
+        for _ in 0..1000 {
+            mutator.mutate_input(&corpus);
+            assert!(!mutator.input.is_empty() && mutator.input.len() <= 4);
+        }
+        assert!(mutator.warned_over_cap);
+    }
+
+    #[test]
+    fn effective_rounds_change_several_spots() {
+        let seed: Vec<u8> = (0..=255).collect();
+        let corpus = Corpus::from_inputs(vec![seed.clone()]);
+        let mut mutator = Mutator::new(Some(0x1337), 256);
+        let mut weights: Vec<_> = MUTATIONS.iter().map(|&ty| (ty, 0)).collect();
+        weights.push((MutationTypes::ByteOverwrite, 1));
+        mutator.set_weights(&weights);
+        mutator.config.require_effective_rounds = 3;
+
+        for _ in 0..1000 {
+            mutator.mutate_input(&corpus);
+            if mutator.last_parent().is_none() {
+                continue;
+            }
+
+            let changed = seed
+                .iter()
+                .zip(&mutator.input)
+                .filter(|(a, b)| a != b)
+                .count();
+            assert!(changed >= 3, "{}", changed);
+        }
+    }
+
+    #[test]
+    fn similar_inputs_share_a_shape() {
+        let a = Mutator::shape_signature(b"GET /index.html HTTP/1.1\r\n");
+        let b = Mutator::shape_signature(b"GET /images.html HTTP/1.0\r\n");
+        let c = Mutator::shape_signature(&[0xFF; 300]);
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn zero_scratch_rate_always_mutates_seeds() {
+        let corpus = Corpus::from_inputs(vec![b"AAAAAAAAAAAAAAAAAAAAAAAA".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        mutator.config.gen_scratch_rate = Some(0);
+
+        // Every output has to be a mutation of the seed, never from scratch
+        for _ in 0..10000 {
+            mutator.mutate_input(&corpus);
+            assert_eq!(mutator.last_parent(), Some(0));
+        }
+    }
+
+    #[test]
+    fn profiling_times_the_strategies_that_ran() {
+        let corpus = Corpus::from_inputs(vec![b"some seed input".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+
+        mutator.mutate_input(&corpus);
+        assert!(mutator.stats().timings().is_empty());
+
+        mutator.config.profile = true;
+        let histogram = mutator.audit_distribution(&corpus, 100);
+        let timings = mutator.stats().timings();
+        assert!(!timings.is_empty());
+
+        // Only strategies that were selected get timed, every time they ran
+        for (ty, _, runs) in timings {
+            assert_eq!(histogram[&format!("{:?}", ty)] as u64, runs);
+        }
+    }
+
+    #[test]
+    fn splice_exclude_self_always_uses_the_other_input() {
+        let corpus = Corpus::from_inputs(vec![vec![b'A'; 8], vec![b'B'; 8]]);
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        mutator.config.splice_exclude_self = true;
+
+        for _ in 0..1000 {
+            mutator.input = vec![b'A'; 8];
+            mutator.splice(&corpus, Some(0));
+            assert!(mutator.input.contains(&b'B'), "{:?}", mutator.input);
+        }
+    }
+
+    #[test]
+    fn radius_one_neighborhood_is_single_edits() {
+        let mut mutator = Mutator::new(Some(1), 64);
+        let neighbors = mutator.neighborhood(b"ABCD", 1);
+
+        assert_eq!(neighbors.len(), 8 * 4 + 2);
+        assert!(neighbors.contains(&b"ABC".to_vec()));
+        assert!(neighbors.contains(&b"ABCD\x00".to_vec()));
+        assert!(neighbors.contains(&b"CBCD".to_vec()));
+        assert_eq!(neighbors, mutator.neighborhood(b"ABCD", 1));
+
+        // Wider radii are capped
+        assert_eq!(mutator.neighborhood(b"ABCD", 8).len(), MAX_NEIGHBORS);
+    }
+
+    #[test]
+    fn invariant_check_passes_with_a_fixup() {
+        let corpus = Corpus::from_inputs(vec![b"\x7FELF header".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        mutator.config.check_invariants = true;
+        mutator.set_invariant_check(Box::new(|input| input[0] == 0x7F));
+        mutator.set_post_processor(Box::new(|input| input[0] = 0x7F));
+
+        for _ in 0..1000 {
+            assert!(mutator.try_mutate(&corpus).is_ok());
+        }
+
+        // Without the fixup the check eventually trips
+        mutator.set_post_processor(Box::new(|_| ()));
+        assert!((0..1000).any(|_| mutator.try_mutate(&corpus).is_err()));
+    }
+
+    #[test]
+    fn large_delete_range_keeps_min_size() {
+        let original: Vec<u8> = (0..4096u32).map(|i| (i % 251) as u8).collect();
+        let mut mutator = Mutator::new(Some(0x1337), 8192);
+        mutator.config.delete_range_fraction = Some(1.0);
+        mutator.config.min_size = 1024;
+
+        let mut largest = 0;
+        for _ in 0..1000 {
+            mutator.input = original.clone();
+            mutator.delete_range();
+
+            // Whatever went missing was one contiguous span
+            let removed = original.len() - mutator.input.len();
+            let start = original
+                .iter()
+                .zip(&mutator.input)
+                .take_while(|(a, b)| a == b)
+                .count();
+            assert_eq!(mutator.input[..start], original[..start]);
+            assert_eq!(mutator.input[start..], original[start + removed..]);
+            assert!(mutator.input.len() >= 1024);
+            largest = largest.max(removed);
+        }
+
+        // Well past what block_delete would ever remove
+        assert!(largest > 2048, "{}", largest);
+    }
+
+    #[test]
+    fn greedy_priority_always_picks_the_top_input() {
+        let mut corpus = Corpus::from_inputs((0..5u8).map(|i| vec![i; 4]).collect());
+        corpus.set_priority(3, 1000.0);
+
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        mutator.config.selection = Selection::Priority { greedy: true };
+        assert!((0..1000).all(|_| mutator.select_input(&corpus) == 3));
+
+        // Proportional selection still favors it heavily
+        mutator.config.selection = Selection::Priority { greedy: false };
+        let top = (0..1000)
+            .filter(|_| mutator.select_input(&corpus) == 3)
+            .count();
+        assert!(top > 950, "{}", top);
+    }
+
+    #[test]
+    fn learned_bigrams_prefer_the_common_pair() {
+        let corpus = Corpus::from_inputs(vec![b"ab;ab;ab;ab;ab".to_vec(), b"abab".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        mutator.learn_bigrams(&corpus);
+
+        let mut counts: HashMap<Vec<u8>, usize> = HashMap::new();
+        for _ in 0..1000 {
+            mutator.input = b"zzzz".to_vec();
+            mutator.bigram_insert();
+            let pair: Vec<u8> = mutator
+                .input
+                .iter()
+                .copied()
+                .filter(|&b| b != b'z')
+                .collect();
+            *counts.entry(pair).or_insert(0) += 1;
+        }
+
+        let (top, count) = counts.iter().max_by_key(|(_, &count)| count).unwrap();
+        assert_eq!(top, b"ab");
+        assert!(*count > 400, "{:?}", counts);
+    }
+
+    #[test]
+    fn crossover_offspring_share_a_point() {
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+        let a = b"AAAAAAAAAAAA";
+        let b = b"bbbbbbbbbbbbbbbbbbbb";
+
+        for _ in 0..100 {
+            let (first, second) = mutator.crossover_pair(a, b);
+            let point = first.iter().take_while(|&&byte| byte == b'A').count();
+
+            assert!(point > 0 && point < a.len());
+            assert_eq!(first, [&a[..point], &b[point..]].concat());
+            assert_eq!(second, [&b[..point], &a[point..]].concat());
+        }
+
+        // Offspring are held to max_size
+        let mut mutator = Mutator::new(Some(0x1337), 8);
+        let (first, second) = mutator.crossover_pair(a, b);
+        assert!(first.len() <= 8 && second.len() <= 8);
+    }
+
+    #[test]
+    fn corpus_walk_yields_each_input_then_none() {
+        let corpus = Corpus::from_inputs(vec![b"one".to_vec(), b"two".to_vec(), b"six".to_vec()]);
+        let mut mutator = Mutator::new(Some(0x1337), 64);
+
+        for _ in 0..2 {
+            assert_eq!(mutator.corpus_walk(&corpus), Some(&b"one"[..]));
+            assert_eq!(mutator.corpus_walk(&corpus), Some(&b"two"[..]));
+            assert_eq!(mutator.corpus_walk(&corpus), Some(&b"six"[..]));
+            assert_eq!(mutator.corpus_walk(&corpus), None);
+        }
+
+        // With a mutation per input, every input is still visited in order
+        mutator.config.walk_mutate = true;
+        for idx in 0..3 {
+            assert!(mutator.corpus_walk(&corpus).is_some());
+            assert_eq!(mutator.last_parent(), Some(idx));
+        }
+        assert!(mutator.corpus_walk(&corpus).is_none());
+    }
+
+    #[test]
+    fn growth_is_skipped_at_capacity() {
+        let mut mutator = Mutator::new(Some(0x1337), 16);
+        mutator.input = vec![0x41; 16];
+
+        let picks: Vec<MutationTypes> = (0..1000).filter_map(|_| mutator.pick_mutation()).collect();
+        assert!(!picks.is_empty());
+        assert!(picks.iter().all(|ty| !ty.is_growth()));
+        assert!(picks.contains(&MutationTypes::ByteOverwrite));
+
+        // Unless growth is explicitly kept around
+        mutator.config.grow_at_capacity = true;
+        assert!((0..1000)
+            .filter_map(|_| mutator.pick_mutation())
+            .any(|ty| ty.is_growth()));
+    }
+
+    #[test]
+    fn preserved_suffix_survives_mutation() {
+        let corpus = Corpus::from_inputs(vec![
+            b"HDR:some body bytes here;CRC!".to_vec(),
+            b"xxxxxxxxxxxxxxxxxxxxxxxxxCRC!".to_vec(),
+        ]);
+        let mut mutator = Mutator::new(Some(0x1337), 128);
+        mutator.config.preserve_suffix = 4;
+
+        for _ in 0..20000 {
+            mutator.mutate_input(&corpus);
+            if mutator.last_parent().is_some() {
+                assert!(
+                    mutator.input.ends_with(b"CRC!"),
+                    "{:?}",
+                    mutator.last_mutation
+                );
+            }
+        }
+
+        // An input that's all prefix and suffix has nothing left to mutate
+        mutator.config.preserve_prefix = 4;
+        let small = Corpus::from_inputs(vec![b"abcdefg".to_vec()]);
+        for _ in 0..100 {
+            mutator.mutate_input(&small);
+            if mutator.last_parent().is_some() {
+                assert_eq!(mutator.input, b"abcdefg");
+            }
+        }
     }
 }
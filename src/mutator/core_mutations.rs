@@ -0,0 +1,1006 @@
+//! The buffer mutation primitives the Mutator is built on. Nothing in here
+//! needs `std` or an allocator, every strategy works on a caller-provided
+//! slice, or a `ByteBuffer` for the strategies that change the length of the
+//! input, and takes the RNG state by reference so these can be lifted into a
+//! `no_std` target as-is
+
+use core::ops::Range;
+
+/// When mutation strategies rely on mutating a number of bytes, this figure
+/// provides the ceiling for how many bytes they are allowed to corrupt. Keep
+/// in mind that inputs may pass through multiple rounds of mutation.
+pub const MAX_BYTE_CORRUPTION: usize = 64;
+
+/// When mutation strategies rely on mutating a block of memory, this figure
+/// provides the ceiling for the dimensions of the block. Keep in mind that
+/// inputs may pass through multiple rounds of mutation.
+pub const MAX_BLOCK_CORRUPTION: usize = 512;
+
+/// When mutation strategies rely on mutating bits, this figure provides the
+/// ceiling for the number of bits that can be affected. Keep in mind that inputs
+/// may pass through multiple rounds of mutation.
+pub const MAX_BIT_CORRUPTION: usize = 64;
+
+/// A growable byte buffer, strategies that insert or remove bytes need one of
+/// these instead of a plain slice. Implement this over a fixed-capacity array
+/// to avoid needing an allocator
+pub trait ByteBuffer {
+    /// Returns a view of the current contents
+    fn as_slice(&self) -> &[u8];
+
+    /// Inserts a byte at `idx`, shifting everything after it to the right
+    fn insert(&mut self, idx: usize, byte: u8);
+
+    /// Removes the byte at `idx`, shifting everything after it to the left
+    fn remove(&mut self, idx: usize);
+
+    /// Removes a range of bytes
+    fn remove_range(&mut self, range: Range<usize>);
+
+    /// Shortens the buffer to `len` bytes
+    fn truncate(&mut self, len: usize);
+
+    /// Returns the number of bytes in the buffer
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    /// Returns whether or not the buffer is empty
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Xorshift pseudo-random function based on Brandon Falk's streams, returns
+/// the current state and advances it for the next call
+#[inline]
+pub fn xorshift(state: &mut usize) -> usize {
+    // Save off current value
+    let curr = *state;
+
+    // Mutate current state with xorshift for next call
+    *state ^= *state << 13;
+    *state ^= *state >> 17;
+    *state ^= *state << 43;
+
+    // Return saved off value
+    curr
+}
+
+/// Fills `out` with consecutive draws from the RNG, the result is exactly
+/// what calling `xorshift` once per slot would have produced
+#[inline]
+pub fn fill_randoms(state: &mut usize, out: &mut [usize]) {
+    for slot in out.iter_mut() {
+        *slot = xorshift(state);
+    }
+}
+
+/// Draws a random byte from `alphabet`, an empty alphabet means any byte.
+/// The draw is remapped modulo the alphabet, so with every byte allowed this
+/// is the same as a plain `% 256`
+#[inline]
+fn pick_byte(rng: &mut usize, alphabet: &[u8]) -> u8 {
+    remap_byte((xorshift(rng) % 256) as u8, alphabet)
+}
+
+/// Maps an already drawn random byte into `alphabet`, an empty alphabet
+/// leaves the byte unchanged
+#[inline]
+pub fn remap_byte(byte: u8, alphabet: &[u8]) -> u8 {
+    if alphabet.is_empty() {
+        byte
+    } else {
+        alphabet[byte as usize % alphabet.len()]
+    }
+}
+
+/// Insert bytes into the input randomly, the bytes are drawn from `alphabet`
+pub fn byte_insert<B: ByteBuffer>(buf: &mut B, rng: &mut usize, max_size: usize, alphabet: &[u8]) {
+    // Defaults to global max, but can be hand tuned
+    const MAX_INSERTS: usize = MAX_BYTE_CORRUPTION;
+
+    // Determine the slack space we have
+    let slack = max_size.saturating_sub(buf.len());
+
+    // If we don't have any slack or anywhere to insert, return
+    if slack == 0 || buf.is_empty() {
+        return;
+    }
+
+    // Determine the ceiling
+    let ceiling = core::cmp::min(slack, MAX_INSERTS);
+
+    // Pick number of bytes to insert, at least 1
+    let insert_num = (xorshift(rng) % ceiling) + 1;
+
+    // Iterate through and apply insertions, duplicate idxs is ok
+    for _ in 0..insert_num {
+        // Pick an index
+        let curr_idx = xorshift(rng) % buf.len();
+
+        // Pick a byte to insert
+        let byte = pick_byte(rng, alphabet);
+
+        // Insert it
+        buf.insert(curr_idx, byte);
+    }
+}
+
+/// Overwrite bytes in the input randomly, the bytes are drawn from `alphabet`
+pub fn byte_overwrite(buf: &mut [u8], rng: &mut usize, alphabet: &[u8]) {
+    // Defaults to global max, but can be hand tuned
+    const MAX_OVERWRITES: usize = MAX_BYTE_CORRUPTION;
+
+    // Nothing to overwrite in an empty input
+    if buf.is_empty() {
+        return;
+    }
+
+    // Determine how many bytes we can overwrite
+    let ceiling = core::cmp::min(buf.len(), MAX_OVERWRITES);
+
+    // Pick a number of bytes to overwrite
+    let overwrite_num = (xorshift(rng) % ceiling) + 1;
+
+    // Draw every index and byte up front, they're consumed in the same order
+    // they would have been drawn one at a time
+    let mut draws = [0usize; MAX_OVERWRITES * 2];
+    let draws = &mut draws[..overwrite_num * 2];
+    fill_randoms(rng, draws);
+
+    // Iterate through and apply overwrites
+    for pair in draws.chunks_exact(2) {
+        // Pick an index
+        let curr_idx = pair[0] % buf.len();
+
+        // Pick a byte to overwrite with
+        let byte = remap_byte((pair[1] % 256) as u8, alphabet);
+
+        // Overwrite it
+        buf[curr_idx] = byte;
+    }
+}
+
+/// Delete bytes in the input randomly
+pub fn byte_delete<B: ByteBuffer>(buf: &mut B, rng: &mut usize) {
+    // Defaults to global max, but can be hand tuned
+    const MAX_DELETES: usize = MAX_BYTE_CORRUPTION;
+
+    // Determine how many bytes we can delete, we always leave one behind
+    let ceiling = core::cmp::min(buf.len().saturating_sub(1), MAX_DELETES);
+
+    // If the ceiling is 0 (the buffer has a byte or less), return
+    if ceiling == 0 {
+        return;
+    }
+
+    // Pick a number of bytes to delete
+    let delete_num = (xorshift(rng) % ceiling) + 1;
+
+    // Iterate through and apply the deletes
+    for _ in 0..delete_num {
+        // Pick an index
+        let curr_idx = xorshift(rng) % buf.len();
+
+        // Remove it
+        buf.remove(curr_idx);
+    }
+}
+
+/// Grabs a block from the input, and insert it randomly somewhere else
+pub fn block_insert<B: ByteBuffer>(buf: &mut B, rng: &mut usize, max_size: usize) {
+    // Defaults to global max, but can be hand tuned
+    const MAX_BLOCK_SIZE: usize = MAX_BLOCK_CORRUPTION;
+    let mut block = [0u8; MAX_BLOCK_SIZE];
+
+    // Determine the slack space in the input we have since we're growing
+    let slack = max_size.saturating_sub(buf.len());
+
+    // If we don't have any slack or anything to copy, return
+    if slack == 0 || buf.is_empty() {
+        return;
+    }
+
+    // Determine a ceiling
+    let mut ceiling = core::cmp::min(slack, MAX_BLOCK_SIZE);
+
+    // If the ceiling is larger than the input, adjust it
+    if ceiling > buf.len() {
+        ceiling = buf.len();
+    }
+
+    // Determine a block size
+    let block_size = (xorshift(rng) % ceiling) + 1;
+
+    // Determine the end range we can start from for the block
+    let max_start = buf.len() - block_size;
+
+    // Determine where to start reading the block
+    let block_start = xorshift(rng) % (max_start + 1);
+
+    // Copy the block into the block array
+    block[..block_size].copy_from_slice(&buf.as_slice()[block_start..block_start + block_size]);
+
+    // Determine where to insert the block, this is picked before we grow so
+    // it's strictly inside of the original input
+    let block_insert = xorshift(rng) % buf.len();
+
+    // Every insert grows the buffer by one, so `block_insert + i` is always at
+    // most the current length and never past the end. We can't exceed
+    // max_size either since the block is no bigger than the slack
+    assert!(block_insert < buf.len());
+    assert!(buf.len() + block_size <= max_size);
+
+    // Use insert calls (slow, but readable and who cares?)
+    for (i, &byte) in block[..block_size].iter().enumerate() {
+        buf.insert(block_insert + i, byte);
+    }
+}
+
+/// Grabs a block from the input and copy it over to another location
+pub fn block_overwrite(buf: &mut [u8], rng: &mut usize) {
+    // Defaults to global max, but can be hand tuned
+    const MAX_BLOCK_SIZE: usize = MAX_BLOCK_CORRUPTION;
+    let mut block = [0u8; MAX_BLOCK_SIZE];
+
+    // Nothing to copy in an empty input
+    if buf.is_empty() {
+        return;
+    }
+
+    // Determine a ceiling of block size
+    let ceiling = core::cmp::min(buf.len(), MAX_BLOCK_SIZE);
+
+    // Pick a block size
+    let block_size = (xorshift(rng) % ceiling) + 1;
+
+    // Determine the end range we can start from for the block reading, but
+    // also this is the block writing start as well
+    let max_start = buf.len() - block_size;
+
+    // Determine where to start reading the block
+    let block_start = xorshift(rng) % (max_start + 1);
+
+    // Copy the block into the block array
+    block[..block_size].copy_from_slice(&buf[block_start..block_start + block_size]);
+
+    // Determine where to start overwriting
+    let overwrite_start = xorshift(rng) % (max_start + 1);
+
+    // Overwrite those bytes
+    buf[overwrite_start..overwrite_start + block_size].copy_from_slice(&block[..block_size]);
+}
+
+/// Removes a random block from the input buffer
+pub fn block_delete<B: ByteBuffer>(buf: &mut B, rng: &mut usize) {
+    // Defaults to global max, but can be hand tuned
+    const MAX_BLOCK_SIZE: usize = MAX_BLOCK_CORRUPTION;
+
+    // Determine how much we can delete, we always leave one byte behind
+    let ceiling = core::cmp::min(buf.len().saturating_sub(1), MAX_BLOCK_SIZE);
+
+    // If we have a ceiling of 0 (the buffer has a byte or less), just return
+    if ceiling == 0 {
+        return;
+    }
+
+    // Pick a block size for deletion
+    let block_size = (xorshift(rng) % ceiling) + 1;
+
+    // Determine the end range to start deleting from
+    let max_start = buf.len() - block_size;
+
+    // Pick a place to start deleting from
+    let block_start = xorshift(rng) % (max_start + 1);
+
+    // Delete that block
+    buf.remove_range(block_start..block_start + block_size);
+}
+
+/// Deletes one contiguous range of up to `fraction` of the input, `fraction`
+/// is clamped to [0.0, 1.0] and we always leave one byte behind
+pub fn delete_range<B: ByteBuffer>(buf: &mut B, rng: &mut usize, fraction: f32) {
+    // Determine how much we can delete
+    let share = (buf.len() as f32 * fraction.clamp(0.0, 1.0)) as usize;
+    let ceiling = core::cmp::min(buf.len().saturating_sub(1), share);
+
+    // Nothing to delete
+    if ceiling == 0 {
+        return;
+    }
+
+    // Pick a range size for deletion
+    let range_size = (xorshift(rng) % ceiling) + 1;
+
+    // Pick a place to start deleting from
+    let range_start = xorshift(rng) % (buf.len() - range_size + 1);
+
+    // Delete that range
+    buf.remove_range(range_start..range_start + range_size);
+}
+
+/// Picks two equal-length, non-overlapping blocks in the input and swaps
+/// them in place, the length of the input is preserved
+pub fn swap(buf: &mut [u8], rng: &mut usize) {
+    // We need room for at least two disjoint 1-byte blocks
+    if buf.len() < 2 {
+        return;
+    }
+
+    // Determine a ceiling of block size, both blocks have to fit
+    let ceiling = core::cmp::min(buf.len() / 2, MAX_BLOCK_CORRUPTION);
+
+    // Pick a block size
+    let block_size = (xorshift(rng) % ceiling) + 1;
+
+    // Pick where the first block starts, leaving room for the second block
+    let first_start = xorshift(rng) % (buf.len() - (block_size * 2) + 1);
+
+    // Pick where the second block starts, somewhere after the first block
+    let second_min = first_start + block_size;
+    let second_start = second_min + xorshift(rng) % (buf.len() - block_size - second_min + 1);
+
+    // Split the input so we can borrow both blocks mutably and swap them
+    let (head, tail) = buf.split_at_mut(second_start);
+    head[first_start..first_start + block_size].swap_with_slice(&mut tail[..block_size]);
+}
+
+/// Picks a block of at least 2 bytes for the whole-block transforms,
+/// returns None if the input is too small to have one
+fn pick_transform_block(buf: &[u8], rng: &mut usize) -> Option<Range<usize>> {
+    // Anything smaller can't change when reversed or rotated
+    if buf.len() < 2 {
+        return None;
+    }
+
+    // Determine a ceiling of block size
+    let ceiling = core::cmp::min(buf.len(), MAX_BLOCK_CORRUPTION);
+
+    // Pick a block size, at least 2
+    let block_size = (xorshift(rng) % (ceiling - 1)) + 2;
+
+    // Pick a start idx
+    let block_start = xorshift(rng) % (buf.len() - block_size + 1);
+
+    Some(block_start..block_start + block_size)
+}
+
+/// Swaps a few random pairs of adjacent bytes in place, the length of the
+/// input is preserved
+pub fn transpose(buf: &mut [u8], rng: &mut usize) {
+    // Defaults to a handful of pairs, but can be hand tuned
+    const MAX_TRANSPOSES: usize = 4;
+
+    // We need at least one adjacent pair
+    if buf.len() < 2 {
+        return;
+    }
+
+    // Pick a number of pairs to swap
+    let num_swaps = (xorshift(rng) % MAX_TRANSPOSES) + 1;
+
+    for _ in 0..num_swaps {
+        // Pick the first byte of the pair
+        let idx = xorshift(rng) % (buf.len() - 1);
+        buf.swap(idx, idx + 1);
+    }
+}
+
+/// Toggles the case of every ASCII letter in a random block of the input,
+/// everything else is left alone so text stays text. The length of the input
+/// is preserved
+pub fn case_flip(buf: &mut [u8], rng: &mut usize) {
+    // Nothing to flip in an empty input
+    if buf.is_empty() {
+        return;
+    }
+
+    // Pick a block size
+    let ceiling = core::cmp::min(buf.len(), MAX_BLOCK_CORRUPTION);
+    let block_size = (xorshift(rng) % ceiling) + 1;
+
+    // Pick a start idx
+    let block_start = xorshift(rng) % (buf.len() - block_size + 1);
+
+    for byte in buf[block_start..block_start + block_size].iter_mut() {
+        if byte.is_ascii_alphabetic() {
+            *byte ^= 0x20;
+        }
+    }
+}
+
+/// Reverses a random block of the input in place
+pub fn reverse(buf: &mut [u8], rng: &mut usize) {
+    let Some(block) = pick_transform_block(buf, rng) else {
+        return;
+    };
+
+    buf[block].reverse();
+}
+
+/// Rotates a random block of the input left or right by a random amount
+pub fn rotate_bytes(buf: &mut [u8], rng: &mut usize) {
+    let Some(block) = pick_transform_block(buf, rng) else {
+        return;
+    };
+
+    // Pick an amount that actually moves bytes, 1 through len - 1
+    let amount = (xorshift(rng) % (block.len() - 1)) + 1;
+
+    // Pick a direction
+    if xorshift(rng).is_multiple_of(2) {
+        buf[block].rotate_left(amount);
+    } else {
+        buf[block].rotate_right(amount);
+    }
+}
+
+/// Copies a random block of the input over another region of the same input,
+/// the regions may overlap but never start at the same index
+pub fn copy_within(buf: &mut [u8], rng: &mut usize) {
+    // We need room for a source and a different destination
+    if buf.len() < 2 {
+        return;
+    }
+
+    // Determine a ceiling of block size, leaving room to move the block
+    let ceiling = core::cmp::min(buf.len() - 1, MAX_BLOCK_CORRUPTION);
+
+    // Pick a block size
+    let block_size = (xorshift(rng) % ceiling) + 1;
+
+    // Determine the end range we can start from, this is at least 1
+    let max_start = buf.len() - block_size;
+
+    // Pick where to copy from
+    let src_start = xorshift(rng) % (max_start + 1);
+
+    // Pick where to copy to, skipping over the source start
+    let mut dst_start = xorshift(rng) % max_start;
+    if dst_start >= src_start {
+        dst_start += 1;
+    }
+
+    // Copy with memmove because of overlap potential
+    buf.copy_within(src_start..src_start + block_size, dst_start);
+}
+
+/// Randomly flips bits in the input buffer
+pub fn bit_flip(buf: &mut [u8], rng: &mut usize) {
+    // Nothing to flip in an empty input
+    if buf.is_empty() {
+        return;
+    }
+
+    // Determine the number of bits in the input
+    let num_bits = buf.len() * 8;
+
+    // Determine the ceiling of what we can flip
+    let ceiling = core::cmp::min(num_bits, MAX_BIT_CORRUPTION);
+
+    // Determine the number of bits to flip (at least 1)
+    let num_flips = (xorshift(rng) % ceiling) + 1;
+
+    // Draw every bit position up front
+    let mut draws = [0usize; MAX_BIT_CORRUPTION];
+    let draws = &mut draws[..num_flips];
+    fill_randoms(rng, draws);
+
+    // Go through and flip bits
+    for draw in draws.iter() {
+        // Choose a random bit to flip
+        let bit_position = draw % num_bits;
+
+        // Calculate which byte this bit is in
+        let byte_index = bit_position / 8;
+
+        // Calculate which bit within the byte to flip
+        let bit_index = bit_position % 8;
+
+        // Flip the bit
+        buf[byte_index] ^= 1 << bit_index;
+    }
+}
+
+/// Flips a random number of distinct bits in the input buffer, unlike
+/// `bit_flip` a bit is never flipped twice in one call so the number of bits
+/// that change is exactly the number of flips chosen
+pub fn bit_flip_distinct(buf: &mut [u8], rng: &mut usize) {
+    // Nothing to flip in an empty input
+    if buf.is_empty() {
+        return;
+    }
+
+    // Determine the number of bits in the input
+    let num_bits = buf.len() * 8;
+
+    // Determine the ceiling of what we can flip
+    let ceiling = core::cmp::min(num_bits, MAX_BIT_CORRUPTION);
+
+    // Determine the number of bits to flip (at least 1)
+    let num_flips = (xorshift(rng) % ceiling) + 1;
+
+    // Remember what we flipped, there are few enough flips that a linear
+    // search beats hashing and we don't need an allocation
+    let mut flipped = [0usize; MAX_BIT_CORRUPTION];
+
+    // Go through and flip bits
+    for i in 0..num_flips {
+        // Choose a random bit we haven't flipped yet, this always terminates
+        // since there are at least `num_flips` bits to choose from
+        let bit_position = loop {
+            let candidate = xorshift(rng) % num_bits;
+            if !flipped[..i].contains(&candidate) {
+                break candidate;
+            }
+        };
+        flipped[i] = bit_position;
+
+        // Flip the bit
+        buf[bit_position / 8] ^= 1 << (bit_position % 8);
+    }
+}
+
+/// Walks the input buffer and replaces each byte with a different one with
+/// probability `prob`, unlike `byte_overwrite` the number of bytes touched
+/// isn't bounded. Replacements are drawn from `alphabet` when there's another
+/// allowed byte to change to
+pub fn scatter(buf: &mut [u8], rng: &mut usize, prob: f32, alphabet: &[u8]) {
+    // Resolution of the probability roll
+    const SCALE: usize = 1_000_000;
+
+    let threshold = (prob.clamp(0.0, 1.0) * SCALE as f32) as usize;
+    if threshold == 0 {
+        return;
+    }
+
+    for byte in buf.iter_mut() {
+        if xorshift(rng) % SCALE < threshold {
+            *byte = pick_other_byte(rng, *byte, alphabet);
+        }
+    }
+}
+
+/// Draws a random byte from `alphabet` (any byte if it's empty) that differs
+/// from `old`, unless `old` is the only byte the alphabet allows
+fn pick_other_byte(rng: &mut usize, old: u8, alphabet: &[u8]) -> u8 {
+    if alphabet.is_empty() {
+        return old.wrapping_add(1 + (xorshift(rng) % 255) as u8);
+    }
+
+    match alphabet.iter().position(|&byte| byte == old) {
+        Some(_) if alphabet.len() == 1 => old,
+        Some(pos) => {
+            let offset = 1 + xorshift(rng) % (alphabet.len() - 1);
+            alphabet[(pos + offset) % alphabet.len()]
+        }
+        None => alphabet[xorshift(rng) % alphabet.len()],
+    }
+}
+
+/// Inserts a run of a random byte drawn from `alphabet` into the input buffer
+pub fn grow<B: ByteBuffer>(buf: &mut B, rng: &mut usize, max_size: usize, alphabet: &[u8]) {
+    // Determine maximum size to grow
+    let slack = max_size.saturating_sub(buf.len());
+    if slack == 0 || buf.is_empty() {
+        return;
+    }
+
+    // Pick size of block
+    let size = (xorshift(rng) % slack) + 1;
+
+    // Pick an index to add to
+    let idx = xorshift(rng) % buf.len();
+
+    // Pick byte to place in there
+    let byte = pick_byte(rng, alphabet);
+
+    // Insert there
+    for _ in 0..size {
+        buf.insert(idx, byte);
+    }
+}
+
+/// Inserts a run of a fixed byte into the input buffer, unlike `grow` the
+/// caller picks the byte so it can be a boundary value like 0x00 or 0xFF
+pub fn insert_run<B: ByteBuffer>(buf: &mut B, rng: &mut usize, max_size: usize, byte: u8) {
+    // Determine the slack space we have
+    let slack = max_size.saturating_sub(buf.len());
+    if slack == 0 || buf.is_empty() {
+        return;
+    }
+
+    // Pick size of the run
+    let size = (xorshift(rng) % core::cmp::min(slack, MAX_BLOCK_CORRUPTION)) + 1;
+
+    // Pick an index to add to
+    let idx = xorshift(rng) % buf.len();
+
+    // Insert there
+    for _ in 0..size {
+        buf.insert(idx, byte);
+    }
+}
+
+/// Truncates the input a random amount of bytes but always leaves at least
+/// one byte
+pub fn truncate<B: ByteBuffer>(buf: &mut B, rng: &mut usize) {
+    // Determine how much we can shrink
+    let slack = buf.len().saturating_sub(1);
+    if slack == 0 {
+        return;
+    }
+
+    // Pick an index to truncate at, can't be zero
+    let idx = (xorshift(rng) % slack) + 1;
+
+    // Truncate
+    buf.truncate(idx);
+}
+
+/// Truncates the input like `truncate` but snaps the chosen length down to the
+/// nearest of the given field boundaries so a field is never cut in half. If
+/// there's no boundary between the chosen length and the start of the input
+/// the input is left alone
+pub fn truncate_to_boundary<B: ByteBuffer>(buf: &mut B, rng: &mut usize, boundaries: &[usize]) {
+    // Determine how much we can shrink
+    let slack = buf.len().saturating_sub(1);
+    if slack == 0 {
+        return;
+    }
+
+    // Pick an index to truncate at, can't be zero
+    let idx = (xorshift(rng) % slack) + 1;
+
+    // Snap down to the nearest boundary, which also can't be zero
+    let Some(boundary) = boundaries
+        .iter()
+        .copied()
+        .filter(|&b| b > 0 && b <= idx)
+        .max()
+    else {
+        return;
+    };
+
+    // Truncate
+    buf.truncate(boundary);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_buffer_is_nop() {
+        let mut rng = 0x1337;
+        let mut buf: Vec<u8> = Vec::new();
+
+        byte_insert(&mut buf, &mut rng, 64, &[]);
+        byte_overwrite(&mut buf, &mut rng, &[]);
+        byte_delete(&mut buf, &mut rng);
+        block_insert(&mut buf, &mut rng, 64);
+        block_overwrite(&mut buf, &mut rng);
+        block_delete(&mut buf, &mut rng);
+        delete_range(&mut buf, &mut rng, 0.5);
+        swap(&mut buf, &mut rng);
+        transpose(&mut buf, &mut rng);
+        case_flip(&mut buf, &mut rng);
+        reverse(&mut buf, &mut rng);
+        rotate_bytes(&mut buf, &mut rng);
+        copy_within(&mut buf, &mut rng);
+        bit_flip(&mut buf, &mut rng);
+        bit_flip_distinct(&mut buf, &mut rng);
+        scatter(&mut buf, &mut rng, 1.0, &[]);
+        grow(&mut buf, &mut rng, 64, &[]);
+        insert_run(&mut buf, &mut rng, 64, 0xFF);
+        truncate(&mut buf, &mut rng);
+        truncate_to_boundary(&mut buf, &mut rng, &[1, 2]);
+
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn length_preserving_ops_keep_length() {
+        let mut rng = 0x1337;
+        let mut buf: Vec<u8> = (0..=255).collect();
+
+        for _ in 0..1000 {
+            byte_overwrite(&mut buf, &mut rng, &[]);
+            block_overwrite(&mut buf, &mut rng);
+            swap(&mut buf, &mut rng);
+            transpose(&mut buf, &mut rng);
+            case_flip(&mut buf, &mut rng);
+            reverse(&mut buf, &mut rng);
+            rotate_bytes(&mut buf, &mut rng);
+            copy_within(&mut buf, &mut rng);
+            bit_flip(&mut buf, &mut rng);
+            bit_flip_distinct(&mut buf, &mut rng);
+            scatter(&mut buf, &mut rng, 0.1, &[]);
+            assert_eq!(buf.len(), 256);
+        }
+    }
+
+    #[test]
+    fn growth_ops_respect_max_size() {
+        let mut rng = 0x1337;
+
+        for _ in 0..1000 {
+            let mut buf = b"lucid".to_vec();
+            byte_insert(&mut buf, &mut rng, 32, &[]);
+            block_insert(&mut buf, &mut rng, 32);
+            grow(&mut buf, &mut rng, 32, &[]);
+            insert_run(&mut buf, &mut rng, 32, 0x00);
+            assert!(buf.len() <= 32);
+        }
+    }
+
+    #[test]
+    fn shrinking_ops_leave_a_byte() {
+        let mut rng = 0x1337;
+
+        for _ in 0..1000 {
+            let mut buf = b"lucid".to_vec();
+            byte_delete(&mut buf, &mut rng);
+            block_delete(&mut buf, &mut rng);
+            delete_range(&mut buf, &mut rng, 1.0);
+            truncate(&mut buf, &mut rng);
+            assert!(!buf.is_empty());
+        }
+    }
+
+    #[test]
+    fn alphabet_is_respected() {
+        let mut rng = 0x1337;
+        let mut buf = vec![b'a'; 64];
+
+        byte_overwrite(&mut buf, &mut rng, b"xyz");
+        scatter(&mut buf, &mut rng, 1.0, b"xyz");
+        assert!(buf.iter().all(|byte| b"xyz".contains(byte)));
+    }
+
+    #[test]
+    fn swap_exchanges_two_blocks() {
+        let original: Vec<u8> = (0..64).collect();
+
+        for seed in 1..100 {
+            let mut rng = seed;
+            let mut buf = original.clone();
+            swap(&mut buf, &mut rng);
+
+            // Replay the draws to find out which blocks were picked
+            let mut rng = seed;
+            let ceiling = core::cmp::min(original.len() / 2, MAX_BLOCK_CORRUPTION);
+            let size = (xorshift(&mut rng) % ceiling) + 1;
+            let first = xorshift(&mut rng) % (original.len() - (size * 2) + 1);
+            let second_min = first + size;
+            let second = second_min + xorshift(&mut rng) % (original.len() - size - second_min + 1);
+
+            let mut expected = original.clone();
+            expected[first..first + size].copy_from_slice(&original[second..second + size]);
+            expected[second..second + size].copy_from_slice(&original[first..first + size]);
+            assert_eq!(buf, expected);
+        }
+
+        // Too small for two disjoint blocks
+        let mut rng = 0x1337;
+        let mut buf = vec![0x41];
+        swap(&mut buf, &mut rng);
+        assert_eq!(buf, [0x41]);
+    }
+
+    #[test]
+    fn reverse_and_rotate_transform_a_known_block() {
+        let original: Vec<u8> = (0..64).collect();
+
+        for seed in 1..100 {
+            // The block both transforms pick is the same for the same seed
+            let mut rng = seed;
+            let block = pick_transform_block(&original, &mut rng).unwrap();
+            let (start, end) = (block.start, block.end);
+
+            let mut rng = seed;
+            let mut buf = original.clone();
+            reverse(&mut buf, &mut rng);
+            let mut expected = original.clone();
+            expected[start..end].reverse();
+            assert_eq!(buf, expected);
+
+            // Replay the rotation amount and direction too
+            let mut rng = seed;
+            pick_transform_block(&original, &mut rng);
+            let k = (xorshift(&mut rng) % (end - start - 1)) + 1;
+            let left = xorshift(&mut rng).is_multiple_of(2);
+            let k = if left { k } else { end - start - k };
+
+            let mut rng = seed;
+            let mut buf = original.clone();
+            rotate_bytes(&mut buf, &mut rng);
+            let rotated = [&original[start + k..end], &original[start..start + k]].concat();
+            assert_eq!(buf[start..end], rotated);
+            assert_eq!(buf[..start], original[..start]);
+            assert_eq!(buf[end..], original[end..]);
+        }
+
+        // Both NOP on tiny inputs
+        let mut rng = 0x1337;
+        let mut buf = vec![0x41];
+        reverse(&mut buf, &mut rng);
+        rotate_bytes(&mut buf, &mut rng);
+        assert_eq!(buf, [0x41]);
+    }
+
+    #[test]
+    fn copy_within_duplicates_a_block() {
+        let original: Vec<u8> = (0..64).collect();
+
+        for seed in 1..100 {
+            let mut rng = seed;
+            let mut buf = original.clone();
+            copy_within(&mut buf, &mut rng);
+
+            // Replay the draws to find out which regions were picked
+            let mut rng = seed;
+            let ceiling = core::cmp::min(original.len() - 1, MAX_BLOCK_CORRUPTION);
+            let size = (xorshift(&mut rng) % ceiling) + 1;
+            let max_start = original.len() - size;
+            let src = xorshift(&mut rng) % (max_start + 1);
+            let mut dst = xorshift(&mut rng) % max_start;
+            if dst >= src {
+                dst += 1;
+            }
+
+            assert_eq!(buf[dst..dst + size], original[src..src + size]);
+            assert_eq!(buf.len(), original.len());
+        }
+
+        // Too small for a different destination
+        let mut rng = 0x1337;
+        let mut buf = vec![0x41];
+        copy_within(&mut buf, &mut rng);
+        assert_eq!(buf, [0x41]);
+    }
+
+    #[test]
+    fn distinct_bit_flips_match_flip_count() {
+        let original = vec![0u8; 4];
+
+        for seed in 1..1000 {
+            let mut rng = seed;
+            let mut buf = original.clone();
+            bit_flip_distinct(&mut buf, &mut rng);
+
+            // The first draw is the number of flips
+            let mut rng = seed;
+            let ceiling = core::cmp::min(original.len() * 8, MAX_BIT_CORRUPTION);
+            let num_flips = (xorshift(&mut rng) % ceiling) + 1;
+
+            let distance: u32 = buf.iter().map(|byte| byte.count_ones()).sum();
+            assert_eq!(distance as usize, num_flips);
+        }
+    }
+
+    #[test]
+    fn insert_run_inserts_boundary_bytes() {
+        let original: Vec<u8> = (1..=32).collect();
+
+        for byte in [0x00, 0xFF] {
+            for seed in 1..100 {
+                let mut rng = seed;
+                let mut buf = original.clone();
+                insert_run(&mut buf, &mut rng, 64, byte);
+
+                // Replay the draws to find out where the run went
+                let mut rng = seed;
+                let size = (xorshift(&mut rng) % core::cmp::min(32, MAX_BLOCK_CORRUPTION)) + 1;
+                let idx = xorshift(&mut rng) % original.len();
+
+                assert_eq!(buf[..idx], original[..idx]);
+                assert!(buf[idx..idx + size].iter().all(|&b| b == byte));
+                assert_eq!(buf[idx + size..], original[idx..]);
+            }
+        }
+    }
+
+    #[test]
+    fn block_insert_property() {
+        let mut rng = 0x1337;
+
+        for len in 1..8 {
+            for max_size in 1..16 {
+                for _ in 0..100 {
+                    let original: Vec<u8> = (0..len as u8).collect();
+                    let mut buf = original.clone();
+
+                    // Replay the draws to find the block and where it went
+                    let mut replay = rng;
+                    block_insert(&mut buf, &mut rng, max_size);
+                    if max_size <= len {
+                        assert_eq!(buf, original);
+                        continue;
+                    }
+
+                    let ceiling = [max_size - len, MAX_BLOCK_CORRUPTION, len]
+                        .into_iter()
+                        .min();
+                    let size = (xorshift(&mut replay) % ceiling.unwrap()) + 1;
+                    let start = xorshift(&mut replay) % (len - size + 1);
+                    let idx = xorshift(&mut replay) % len;
+
+                    let mut expected = original.clone();
+                    expected.splice(idx..idx, original[start..start + size].iter().copied());
+                    assert_eq!(buf, expected);
+                    assert!(buf.len() <= max_size);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn scatter_probability_bounds() {
+        let mut rng = 0x1337;
+        let original: Vec<u8> = (0..=255).collect();
+
+        let mut buf = original.clone();
+        scatter(&mut buf, &mut rng, 1.0, &[]);
+        assert!(buf.iter().zip(&original).all(|(a, b)| a != b));
+
+        let mut buf = original.clone();
+        scatter(&mut buf, &mut rng, 0.0, &[]);
+        assert_eq!(buf, original);
+    }
+
+    #[test]
+    fn transpose_swaps_an_adjacent_pair() {
+        let original: Vec<u8> = (0..64).collect();
+        let mut checked = 0;
+
+        for seed in 1..100 {
+            // Work out which pair a single-swap draw picks
+            let mut draws = seed;
+            if !xorshift(&mut draws).is_multiple_of(4) {
+                continue;
+            }
+            let idx = xorshift(&mut draws) % 63;
+
+            let mut rng = seed;
+            let mut buf = original.clone();
+            transpose(&mut buf, &mut rng);
+
+            let mut expected = original.clone();
+            expected.swap(idx, idx + 1);
+            assert_eq!(buf, expected);
+            checked += 1;
+        }
+        assert!(checked > 0);
+
+        // A single byte has no pair to swap
+        let mut rng = 0x1337;
+        let mut buf = vec![0x41];
+        transpose(&mut buf, &mut rng);
+        assert_eq!(buf, [0x41]);
+    }
+
+    #[test]
+    fn case_flip_only_touches_letters() {
+        let original = b"Get / HTTP";
+        let mut flipped_any = false;
+
+        for seed in 1..100 {
+            let mut rng = seed;
+            let mut buf = original.to_vec();
+            case_flip(&mut buf, &mut rng);
+
+            for (new, old) in buf.iter().zip(original) {
+                if old.is_ascii_alphabetic() {
+                    assert!(new.eq_ignore_ascii_case(old));
+                    flipped_any |= new != old;
+                } else {
+                    assert_eq!(new, old);
+                }
+            }
+        }
+
+        assert!(flipped_any);
+    }
+}
@@ -0,0 +1,187 @@
+//! This file contains a corpus that lives in a single packed file rather than
+//! a directory of files. The file is just length-prefixed records:
+//! `[u32 len][len bytes][u32 len][len bytes]...` with little-endian lengths.
+//! It's mmapped read-only so inputs are handed out without being copied and
+//! only the pages that are actually touched get read in
+
+use std::io::Write;
+use std::os::unix::io::AsRawFd;
+
+use crate::corpus::{Corpus, CorpusView};
+use crate::err::LucidErr;
+
+/// Size of the length prefix in front of each record
+const LEN_PREFIX: usize = std::mem::size_of::<u32>();
+
+/// A read-only corpus backed by an mmapped packed file
+pub struct PackedCorpus {
+    map_base: usize,              // Base address of the mapping, 0 if empty
+    map_length: usize,            // Length of the mapping
+    records: Vec<(usize, usize)>, // Offset and length of each record
+}
+
+impl PackedCorpus {
+    /// Maps a packed corpus file and indexes its records, this only reads the
+    /// length prefixes so opening is cheap relative to the size of the file
+    pub fn open(path: &std::path::Path) -> Result<Self, LucidErr> {
+        let file = std::fs::File::open(path).map_err(|e| {
+            LucidErr::from(&format!(
+                "Unable to open packed corpus '{}', error: {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        let map_length = file
+            .metadata()
+            .map_err(|e| LucidErr::from(&format!("Unable to stat packed corpus: {}", e)))?
+            .len() as usize;
+
+        // Can't mmap an empty file, it's just an empty corpus
+        if map_length == 0 {
+            return Ok(PackedCorpus {
+                map_base: 0,
+                map_length: 0,
+                records: Vec::new(),
+            });
+        }
+
+        // Try to `mmap` the file
+        let result = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                map_length,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+
+        if result == libc::MAP_FAILED {
+            return Err(LucidErr::from("Failed to `mmap` packed corpus"));
+        }
+
+        // From here on out Drop takes care of the mapping
+        let mut packed = PackedCorpus {
+            map_base: result as usize,
+            map_length,
+            records: Vec::new(),
+        };
+
+        // Walk the length prefixes to find each record
+        let data = packed.data();
+        let mut records = Vec::new();
+        let mut offset = 0;
+        while offset < data.len() {
+            if offset + LEN_PREFIX > data.len() {
+                return Err(LucidErr::from("Truncated record length in packed corpus"));
+            }
+
+            let mut len_bytes = [0u8; LEN_PREFIX];
+            len_bytes.copy_from_slice(&data[offset..offset + LEN_PREFIX]);
+            let len = u32::from_le_bytes(len_bytes) as usize;
+
+            let start = offset + LEN_PREFIX;
+            if start + len > data.len() {
+                return Err(LucidErr::from("Truncated record in packed corpus"));
+            }
+
+            records.push((start, len));
+            offset = start + len;
+        }
+
+        packed.records = records;
+        Ok(packed)
+    }
+
+    /// The whole mapped file
+    fn data(&self) -> &[u8] {
+        if self.map_length == 0 {
+            return &[];
+        }
+
+        unsafe { std::slice::from_raw_parts(self.map_base as *const u8, self.map_length) }
+    }
+}
+
+impl CorpusView for PackedCorpus {
+    fn num_inputs(&self) -> usize {
+        self.records.len()
+    }
+
+    fn get_input(&self, idx: usize) -> Option<&[u8]> {
+        let &(start, len) = self.records.get(idx)?;
+        Some(&self.data()[start..start + len])
+    }
+}
+
+impl Drop for PackedCorpus {
+    fn drop(&mut self) {
+        if self.map_length != 0 {
+            unsafe {
+                libc::munmap(self.map_base as *mut libc::c_void, self.map_length);
+            }
+        }
+    }
+}
+
+/// Writes every in-memory input of a corpus to a packed corpus file that can
+/// be opened with `PackedCorpus::open`
+pub fn pack_corpus(corpus: &Corpus, path: &std::path::Path) -> Result<(), LucidErr> {
+    let file = std::fs::File::create(path).map_err(|e| {
+        LucidErr::from(&format!(
+            "Unable to create packed corpus '{}', error: {}",
+            path.display(),
+            e
+        ))
+    })?;
+
+    let mut writer = std::io::BufWriter::new(file);
+    for input in corpus.inputs.iter() {
+        let Ok(len) = u32::try_from(input.len()) else {
+            return Err(LucidErr::from("Input too large for a packed corpus record"));
+        };
+
+        writer
+            .write_all(&len.to_le_bytes())
+            .and_then(|_| writer.write_all(input))
+            .map_err(|e| LucidErr::from(&format!("Unable to write packed corpus: {}", e)))?;
+    }
+
+    writer
+        .flush()
+        .map_err(|e| LucidErr::from(&format!("Unable to write packed corpus: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packed_corpus_round_trip() {
+        let inputs = vec![b"lucid".to_vec(), vec![0; 1000], b"x".to_vec()];
+        let corpus = Corpus::from_inputs(inputs.clone());
+
+        let path = std::env::temp_dir().join(format!("lucid_packed_{}", std::process::id()));
+        pack_corpus(&corpus, &path).unwrap();
+
+        let packed = PackedCorpus::open(&path).unwrap();
+        assert_eq!(packed.num_inputs(), inputs.len());
+        for (idx, input) in inputs.iter().enumerate() {
+            assert_eq!(packed.get_input(idx), Some(input.as_slice()));
+        }
+        assert_eq!(packed.get_input(inputs.len()), None);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn truncated_pack_is_rejected() {
+        let path = std::env::temp_dir().join(format!("lucid_packed_bad_{}", std::process::id()));
+        std::fs::write(&path, [5, 0, 0, 0, b'a', b'b']).unwrap();
+        assert!(PackedCorpus::open(&path).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -47,6 +47,52 @@ pub struct CorpusStats {
     pub max_input: usize,
 }
 
+/// A cheap execution counter for status lines, `record` every execution and
+/// poll `report` for the executions per second since the last report
+#[derive(Clone, Copy, Debug)]
+pub struct ExecCounter {
+    pub total: u64,           // Executions over the counter's lifetime
+    pub since_last: u64,      // Executions since the last report
+    pub last_report: Instant, // When the last report was made
+}
+
+impl ExecCounter {
+    pub fn new() -> Self {
+        ExecCounter {
+            total: 0,
+            since_last: 0,
+            last_report: Instant::now(),
+        }
+    }
+
+    /// Counts one execution
+    #[inline]
+    pub fn record(&mut self) {
+        self.total += 1;
+        self.since_last += 1;
+    }
+
+    /// Returns the executions per second since the last report, at most once
+    /// a second, None if a second hasn't passed yet
+    pub fn report(&mut self) -> Option<f64> {
+        let elapsed = self.last_report.elapsed();
+        if elapsed < Duration::from_secs(1) {
+            return None;
+        }
+
+        let rate = self.since_last as f64 / elapsed.as_secs_f64();
+        self.since_last = 0;
+        self.last_report = Instant::now();
+        Some(rate)
+    }
+}
+
+impl Default for ExecCounter {
+    fn default() -> Self {
+        ExecCounter::new()
+    }
+}
+
 /// Represents the statistics that fuzzers need to serialize to disk if fuzzing
 /// is multi-process. The report number is written first and last as a cheap
 /// file lock so the reader knows that if those are mismatched to re-read
@@ -187,6 +233,8 @@ pub struct Stats {
     pub batch_coverage: Duration, // Batch time spent in coverage
     pub batch_redqueen: Duration, // Batch time spent in redqueen
     pub oldest_batch: Duration,   // Oldest batch duration in multi-process
+    execs: ExecCounter,           // Executions for the single-process iters/s
+    iters_per_sec: f64,           // Iters/s as of the last single-process report
 
     pub edges: usize,        // Number of edges we've hit
     map_size: usize,         // Size of coverage map
@@ -235,7 +283,6 @@ impl Stats {
         // For single process
         let batch_elapsed = self.batch_start.unwrap().elapsed();
         let batch_millis = batch_elapsed.as_millis() as f64;
-        let batch_seconds = batch_millis / 1000.0;
 
         // For multi-process
         let oldest_millis = self.oldest_batch.as_millis() as f64;
@@ -243,7 +290,7 @@ impl Stats {
 
         // Iters/s is based on report mode
         let iters_sec = if matches!(self.report_mode, ReportMode::Single) {
-            self.batch_iters as f64 / batch_seconds
+            self.iters_per_sec
         } else {
             self.batch_iters as f64 / oldest_seconds
         };
@@ -410,6 +457,7 @@ impl Stats {
         self.session_start = Some(Instant::now());
         self.batch_start = Some(Instant::now());
         self.last_find = Some(Instant::now());
+        self.execs = ExecCounter::new();
         self.map_size = map_size;
         self.dirty_block_length = dirty_block_length;
         self.max_input = input_max_size;
@@ -421,6 +469,7 @@ impl Stats {
         // We just completed a single fuzzcase
         self.session_iters += 1;
         self.batch_iters += 1;
+        self.execs.record();
 
         // Update the snapshot statistics
         self.dirty_pages = snapshot.dirty_pages;
@@ -475,6 +524,11 @@ impl Stats {
 
     /// Report stats in single-process fuzzing
     fn report_single(&mut self) -> Result<(), LucidErr> {
+        // Keep the last rate if a second hasn't passed since it was taken
+        if let Some(rate) = self.execs.report() {
+            self.iters_per_sec = rate;
+        }
+
         // Print our stats
         self.print_stats();
 
@@ -712,3 +766,25 @@ impl Stats {
         self.oldest_batch = Duration::new(0, 0);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exec_counter_reports_a_plausible_rate() {
+        let mut counter = ExecCounter::new();
+        for _ in 0..1000 {
+            counter.record();
+        }
+
+        // Nothing is reported until a second has passed
+        assert_eq!(counter.report(), None);
+
+        counter.last_report = Instant::now() - Duration::from_secs(2);
+        let rate = counter.report().unwrap();
+        assert!((400.0..=500.0).contains(&rate), "{}", rate);
+        assert_eq!(counter.total, 1000);
+        assert_eq!(counter.since_last, 0);
+    }
+}